@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use crate::{
+    api::{self, AtcoderApi, JudgeStatus},
+    notify,
+};
+use anyhow::Error;
+use chrono::{DateTime, Duration};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// 集計する日数
+const ACTIVITY_DAYS: u32 = 30;
+
+/// AC数に応じて使うブロック。1問以上解いた日は、最も多く解いた日を基準に高さを決めます。
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// 1問も解かなかった日の記号
+const IDLE: char = '·';
+
+/// 日ごとのAC数を、1日1文字の棒グラフにします。
+fn sparkline(counts: &[usize]) -> String {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|&count| {
+            if count == 0 {
+                IDLE
+            } else {
+                BLOCKS[(count * BLOCKS.len()).div_ceil(max) - 1]
+            }
+        })
+        .collect()
+}
+
+/// ユーザーが直近30日間に毎日ACした問題の数を、棒グラフにした埋め込みを作ります。
+pub async fn activity_embed(
+    api: &dyn AtcoderApi,
+    user: &str,
+    tz: Tz,
+) -> Result<CreateEmbed, Error> {
+    let start = notify::start_of_day(tz, (ACTIVITY_DAYS - 1).into());
+    let submissions = api::fetch_submission_history(api, user, start.timestamp()).await?;
+
+    let mut solved = vec![HashSet::new(); ACTIVITY_DAYS as usize];
+    for s in submissions.iter().filter(|s| s.result == JudgeStatus::Ac) {
+        let Some(date) = DateTime::from_timestamp(s.epoch_second, 0) else {
+            continue;
+        };
+        let day = (date.with_timezone(&tz).date_naive() - start.date_naive()).num_days();
+        if let Some(problems) = usize::try_from(day).ok().and_then(|d| solved.get_mut(d)) {
+            problems.insert(s.problem_id.as_str());
+        }
+    }
+    let counts = solved.iter().map(|p| p.len()).collect::<Vec<_>>();
+    let active_days = counts.iter().filter(|&&c| c > 0).count();
+    let max = counts.iter().copied().max().unwrap_or(0);
+
+    let end = start + Duration::days((ACTIVITY_DAYS - 1).into());
+    let description = if active_days == 0 {
+        format!("過去{}日間にACした問題はありません。", ACTIVITY_DAYS)
+    } else {
+        format!(
+            "`{}`\n{} 〜 {}\n\nACした日: {} / {} 日\n1日の最多AC: {} 問\n合計: {} 問",
+            sparkline(&counts),
+            start.format("%m/%d"),
+            end.format("%m/%d"),
+            active_days,
+            ACTIVITY_DAYS,
+            max,
+            counts.iter().sum::<usize>()
+        )
+    };
+    Ok(CreateEmbed::default()
+        .title(format!(
+            "{} さんの過去{}日間の活動",
+            submissions.first().map_or(user, |s| s.user_id.as_str()),
+            ACTIVITY_DAYS
+        ))
+        .url(api::user_page_url(user))
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(
+            counts.iter().map(|c| c.to_string()).join(" "),
+        )))
+}
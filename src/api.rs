@@ -0,0 +1,1133 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc, Mutex, OnceLock},
+};
+
+use crate::{
+    bot_config::{self, LogLevel},
+    config_path, metrics,
+};
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local};
+use itertools::Itertools;
+use reqwest::{header::RETRY_AFTER, Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, sleep_until, Instant};
+
+#[allow(unused)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ProblemModelItem {
+    pub slope: Option<f64>,
+    pub intercept: Option<f64>,
+    pub variance: Option<f64>,
+    pub difficulty: Option<i64>,
+    pub discrimination: Option<f64>,
+    pub irt_loglikelihood: Option<f64>,
+    pub irt_users: Option<i64>,
+    pub is_experimental: Option<bool>,
+}
+
+#[allow(unused)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ProblemItem {
+    pub id: String,
+    pub contest_id: String,
+    pub problem_index: String,
+    pub name: String,
+    pub title: String,
+}
+
+impl ProblemItem {
+    /// 問題一覧にまだ載っていない問題の情報を、問題IDから推測します。
+    /// `abc300_a` ならコンテストIDは `abc300`、問題番号は `A`、タイトルは問題IDそのものです。
+    pub fn guess(id: &str) -> Self {
+        let (contest_id, index) = id.rsplit_once('_').unwrap_or((id, ""));
+        ProblemItem {
+            id: id.to_string(),
+            contest_id: contest_id.to_string(),
+            problem_index: index.to_uppercase(),
+            name: id.to_string(),
+            title: id.to_string(),
+        }
+    }
+}
+
+#[allow(unused)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ContestItem {
+    pub id: String,
+    pub start_epoch_second: i64,
+    pub duration_second: i64,
+    pub title: String,
+    pub rate_change: String,
+}
+
+impl ContestItem {
+    /// 提出時刻がコンテストの開催期間中かどうかを返します。
+    pub fn is_during(&self, epoch_second: i64) -> bool {
+        (self.start_epoch_second..self.start_epoch_second + self.duration_second)
+            .contains(&epoch_second)
+    }
+}
+
+/// AtCoderのコンテスト成績表の1行
+#[allow(unused)]
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContestResult {
+    pub is_rated: bool,
+    pub place: i64,
+    pub old_rating: i64,
+    pub new_rating: i64,
+    pub performance: i64,
+    pub contest_screen_name: String,
+    pub contest_name: String,
+}
+
+#[allow(unused)]
+#[derive(Clone, Deserialize, Debug)]
+pub struct VirtualContestInfo {
+    pub id: String,
+    pub title: String,
+    pub start_epoch_second: i64,
+    pub duration_second: i64,
+    pub penalty_second: i64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Deserialize, Debug)]
+pub struct VirtualContestProblem {
+    pub id: String,
+    pub point: Option<f64>,
+    pub order: Option<i64>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct VirtualContest {
+    pub info: VirtualContestInfo,
+    pub problems: Vec<VirtualContestProblem>,
+    pub participants: Vec<String>,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JudgeStatus {
+    Ce,
+    Mle,
+    Tle,
+    Re,
+    Ole,
+    Ie,
+    Wa,
+    Ac,
+    Wj,
+    Wr,
+}
+
+#[allow(unused)]
+#[derive(Clone, Deserialize, Debug)]
+pub struct SubmissionItem {
+    pub id: i64,
+    pub epoch_second: i64,
+    pub problem_id: String,
+    pub contest_id: String,
+    pub user_id: String,
+    pub language: String,
+    pub point: f64,
+    pub length: i64,
+    pub result: JudgeStatus,
+    pub execution_time: Option<i64>,
+}
+
+/// submissions APIが一度に返す提出の最大件数
+const SUBMISSIONS_PAGE_SIZE: usize = 500;
+
+/// 難易度の推定に使われたユーザーがこれより少ないと、信頼度が低いとみなします
+const LOW_CONFIDENCE_IRT_USERS: i64 = 100;
+
+/// 難易度の推定の分散がこれより大きいと、信頼度が低いとみなします
+const LOW_CONFIDENCE_VARIANCE: f64 = 0.5;
+
+/// 問題データのキャッシュを再取得するまでの時間
+const PROBLEM_DATA_TTL: Duration = Duration::hours(1);
+
+/// 失敗したリクエストを再試行する回数
+const HTTP_RETRIES: u32 = 3;
+
+/// 再試行までの待ち時間の初期値。再試行のたびに2倍になります。
+const HTTP_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// 429 Too Many Requestsが返ったときに再試行する回数
+const RATE_LIMIT_RETRIES: u32 = 2;
+
+/// `Retry-After` がないときに、429の後で再試行するまでの待ち時間
+const RATE_LIMIT_DEFAULT_WAIT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// `Retry-After` に従って待つ時間の上限
+const RATE_LIMIT_MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// HTTPリクエスト1回あたりのタイムアウト
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// リクエストに付ける `User-Agent` (例: `atcoder-bot-rs/0.1.0 (+https://github.com/...)`)
+const USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (+",
+    env!("CARGO_PKG_REPOSITORY"),
+    ")"
+);
+
+/// 問題データのキャッシュを保存するファイルの名前
+const PROBLEM_CACHE_FILE: &str = "problems_cache.json";
+
+/// AtCoder ProblemsのAPIのURLの既定値
+const DEFAULT_KENKOOOO_BASE_URL: &str = "https://kenkoooo.com/atcoder";
+
+/// submissions APIへのリクエストの最小間隔の既定値。
+/// AtCoder Problemsのドキュメントで、1秒以上空けるよう求められています。
+/// 環境変数 `SUBMISSIONS_INTERVAL_MS` で変更できます。
+const DEFAULT_SUBMISSIONS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// submissions APIへ次にリクエストを送ってよい時刻
+static NEXT_SUBMISSIONS_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+static PROBLEM_DATA: Mutex<Option<Arc<ProblemData>>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize)]
+pub struct ProblemData {
+    pub problems: HashMap<String, ProblemItem>,
+    pub problem_models: HashMap<String, ProblemModelItem>,
+    pub contests: HashMap<String, ContestItem>,
+    pub fetched_at: DateTime<Local>,
+}
+
+impl ProblemData {
+    /// 問題の難易度を返します。推定値の場合は `true` を添えます。
+    ///
+    /// 次の順に調べ、どれも見つからなければ `None` を返します。
+    /// 1. 難易度モデルの難易度
+    /// 2. 同じシリーズ (abc, arc など) の同じ問題番号の問題の、難易度の中央値
+    pub fn difficulty(&self, problem_id: &str) -> Option<(i64, bool)> {
+        if let Some(d) = self
+            .problem_models
+            .get(problem_id)
+            .and_then(|m| m.difficulty)
+        {
+            return Some((d, false));
+        }
+        self.series_difficulty(problem_id).map(|d| (d, true))
+    }
+
+    /// 難易度の推定の信頼度が低いかどうかを返します。
+    /// 試験的な推定値か、推定に使われたユーザーが少ないか、分散が大きい場合に信頼度が低いとします。
+    pub fn is_low_confidence(&self, problem_id: &str) -> bool {
+        let Some(model) = self.problem_models.get(problem_id) else {
+            return false;
+        };
+        model.is_experimental.unwrap_or(false)
+            || model
+                .irt_users
+                .is_some_and(|users| users < LOW_CONFIDENCE_IRT_USERS)
+            || model
+                .variance
+                .is_some_and(|variance| variance > LOW_CONFIDENCE_VARIANCE)
+    }
+
+    /// 同じシリーズの同じ問題番号の問題の、難易度の中央値を返します。
+    fn series_difficulty(&self, problem_id: &str) -> Option<i64> {
+        let problem = self.problems.get(problem_id)?;
+        let series = contest_series(&problem.contest_id);
+        let difficulties = self
+            .problems
+            .values()
+            .filter(|p| p.problem_index == problem.problem_index)
+            .filter(|p| contest_series(&p.contest_id) == series)
+            .filter_map(|p| self.problem_models.get(&p.id)?.difficulty)
+            .sorted()
+            .collect::<Vec<_>>();
+        difficulties.get(difficulties.len() / 2).copied()
+    }
+}
+
+/// コンテストIDから、末尾の回数を除いたシリーズ名を返します (`abc300` なら `abc`)。
+fn contest_series(contest_id: &str) -> &str {
+    contest_id.trim_end_matches(|c: char| c.is_ascii_digit())
+}
+
+/// 問題番号を並べ替えるためのキーを返します。
+/// 1文字の番号 (A〜Z) を先に、`F2` のように数字が付くものはその文字の後に並べます。
+/// ABCの `Ex` はH問題として扱います。
+pub fn problem_index_key(index: &str) -> (usize, String, u32) {
+    let index = if index == "Ex" { "H" } else { index };
+    let letters = index
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    let number = index[letters.len()..].parse().unwrap_or(0);
+    (letters.len(), letters, number)
+}
+
+/// 429 Too Many Requestsが返り続け、リクエストを諦めたことを表すエラー
+#[derive(Debug)]
+pub struct RateLimited {
+    /// `Retry-After` で指定された待ち時間
+    pub retry_after: Option<std::time::Duration>,
+}
+
+impl Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rate limited (Retry-After: {:?})", self.retry_after)
+    }
+}
+
+/// GETリクエストを送り、JSONとして解析します。
+/// 接続エラー・サーバーエラー・解析の失敗は、間隔を空けながら再試行します。
+/// 429が返った場合は `Retry-After` だけ待って再試行し、それでも駄目なら [`RateLimited`] を返します。
+pub async fn http_get<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, Error> {
+    let mut backoff = HTTP_RETRY_BACKOFF;
+    let mut retries = 0;
+    let mut rate_limit_retries = 0;
+    loop {
+        let res = try_http_get(url).await;
+        let Err(e) = &res else {
+            return res;
+        };
+        if let Some(limited) = e.downcast_ref::<RateLimited>() {
+            if rate_limit_retries == RATE_LIMIT_RETRIES {
+                println!("Request to {} is still rate limited, giving up", url);
+                return res;
+            }
+            let wait = limited
+                .retry_after
+                .unwrap_or(RATE_LIMIT_DEFAULT_WAIT)
+                .min(RATE_LIMIT_MAX_WAIT);
+            println!("Request to {} rate limited, retrying in {:?}", url, wait);
+            sleep(wait).await;
+            rate_limit_retries += 1;
+        } else if is_retryable(e) && retries < HTTP_RETRIES {
+            println!(
+                "Request to {} failed, retrying in {:?}: {:?}",
+                url, backoff, e
+            );
+            sleep(backoff).await;
+            backoff *= 2;
+            retries += 1;
+        } else {
+            return res;
+        }
+    }
+}
+
+async fn try_http_get<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, Error> {
+    metrics::API_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    if bot_config::log_level() >= LogLevel::Debug {
+        println!("GET {}", url);
+    }
+    let res = send_http_get(url).await;
+    if res.is_err() {
+        metrics::API_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+    res
+}
+
+/// すべてのリクエストで共有するクライアントを返します。
+/// 使い回すことで、接続やTLSのハンドシェイクを再利用できます。
+pub fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .gzip(true)
+            .user_agent(USER_AGENT)
+            .build()
+            .expect("Failed to build HTTP client")
+    })
+}
+
+async fn send_http_get<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, Error> {
+    let res = client().get(url).send().await?;
+    if res.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_secs);
+        return Err(Error::msg(RateLimited { retry_after }));
+    }
+    let res = res.error_for_status()?.text().await?;
+    parse_response(url, &res)
+}
+
+/// 応答をJSONとして解析します。失敗した場合は、原因を調べられるよう応答の先頭をログに残します。
+fn parse_response<T: for<'de> Deserialize<'de>>(url: &str, res: &str) -> Result<T, Error> {
+    serde_json::from_str::<T>(res).map_err(|e| {
+        println!(
+            "Failed to parse response from {}: {}",
+            url,
+            res.chars().take(200).collect::<String>()
+        );
+        Error::new(e).context(format!("Failed to parse response from {}", url))
+    })
+}
+
+fn is_retryable(e: &Error) -> bool {
+    match e.downcast_ref::<reqwest::Error>() {
+        Some(e) => {
+            e.is_connect() || e.is_timeout() || e.status().is_some_and(|s| s.is_server_error())
+        }
+        None => e.is::<serde_json::Error>(),
+    }
+}
+
+/// AtCoder ProblemsのAPIのURLを返します。
+/// 環境変数 `KENKOOOO_BASE_URL` で、テスト用のサーバーなどに向け先を変更できます。
+/// 環境変数は呼ばれるたびに読むので、後から変更した向け先もすぐに使われます。
+fn kenkoooo_url(path: &str) -> String {
+    // テストでは本物のAPIにリクエストを送らないよう、URLを作る前に必ずテスト用のサーバーを起動します
+    #[cfg(test)]
+    crate::mock_server::server();
+    let base_url = std::env::var("KENKOOOO_BASE_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or(DEFAULT_KENKOOOO_BASE_URL.into());
+    format!("{}{}", base_url, path)
+}
+
+/// 提出が1件も返らなかったとき、一時的な不具合でないか確かめるために再取得するまで待つ時間。
+/// 環境変数 `CONFIRM_EMPTY_SUBMISSIONS_MS` で指定した場合だけ再取得し、既定では再取得しません。
+fn confirm_empty_delay() -> Option<std::time::Duration> {
+    static DELAY: OnceLock<Option<std::time::Duration>> = OnceLock::new();
+    *DELAY.get_or_init(|| {
+        std::env::var("CONFIRM_EMPTY_SUBMISSIONS_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(std::time::Duration::from_millis)
+    })
+}
+
+/// submissions APIへのリクエストの最小間隔を返します。
+fn submissions_interval() -> std::time::Duration {
+    static INTERVAL: OnceLock<std::time::Duration> = OnceLock::new();
+    *INTERVAL.get_or_init(|| {
+        std::env::var("SUBMISSIONS_INTERVAL_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(std::time::Duration::from_millis)
+            .unwrap_or(DEFAULT_SUBMISSIONS_INTERVAL)
+    })
+}
+
+/// submissions APIへのリクエストが `interval` 以上の間隔で送られるよう、順番が来るまで待ちます。
+/// `next_request` には次のリクエストを送ってよい時刻を記録し、同時に呼ばれた場合も、
+/// それぞれ別の時刻が割り当てられます。
+async fn wait_submissions_turn(
+    next_request: &Mutex<Option<Instant>>,
+    interval: std::time::Duration,
+) {
+    let turn = {
+        let mut next = next_request.lock().unwrap();
+        let turn = next.map_or(Instant::now(), |next| next.max(Instant::now()));
+        *next = Some(turn + interval);
+        turn
+    };
+    sleep_until(turn).await;
+}
+
+/// submissions APIのURL。ユーザー名はクエリの値としてエンコードします。
+fn submissions_url(user: &str, from_second: i64) -> Result<Url, Error> {
+    Ok(Url::parse_with_params(
+        &kenkoooo_url("/atcoder-api/v3/user/submissions"),
+        &[("user", user), ("from_second", &from_second.to_string())],
+    )?)
+}
+
+/// `from_second` 以降のユーザーの提出を最大500件取得します。
+pub async fn fetch_submissions(user: &str, from_second: i64) -> Result<Vec<SubmissionItem>, Error> {
+    wait_submissions_turn(&NEXT_SUBMISSIONS_REQUEST, submissions_interval()).await;
+    http_get(submissions_url(user, from_second)?.as_str()).await
+}
+
+/// バーチャルコンテストの情報を取得します。
+pub async fn fetch_virtual_contest(id: &str) -> Result<VirtualContest, Error> {
+    let mut url = Url::parse(&kenkoooo_url("/internal-api/contest/get"))?;
+    url.path_segments_mut().unwrap().push(id);
+    http_get(url.as_str()).await
+}
+
+/// 指定したユーザーたちの、指定した問題への期間内の提出を取得します。
+pub async fn fetch_users_and_time(
+    users: &[String],
+    problems: &[String],
+    from_second: i64,
+    to_second: i64,
+) -> Result<Vec<SubmissionItem>, Error> {
+    let url = Url::parse_with_params(
+        &kenkoooo_url("/atcoder-api/v3/users_and_time"),
+        &[
+            ("users", users.join(",")),
+            ("problems", problems.join(",")),
+            ("from", from_second.to_string()),
+            ("to", to_second.to_string()),
+        ],
+    )?;
+    http_get(url.as_str()).await
+}
+
+/// AC数ランキングの1人分
+#[allow(unused)]
+#[derive(Clone, Deserialize, Debug)]
+pub struct RankingItem {
+    pub user_id: String,
+    pub count: i64,
+}
+
+/// AC数ランキングの `from` 位から `to` 位の手前までを取得します。
+pub async fn fetch_ac_ranking(from: usize, to: usize) -> Result<Vec<RankingItem>, Error> {
+    let url = Url::parse_with_params(
+        &kenkoooo_url("/atcoder-api/v3/ac_ranking"),
+        &[("from", from.to_string()), ("to", to.to_string())],
+    )?;
+    http_get(url.as_str()).await
+}
+
+/// ユーザーのランキング上の値と順位
+#[derive(Clone, Deserialize, Debug)]
+pub struct UserRankItem {
+    pub count: f64,
+    pub rank: i64,
+}
+
+/// ユーザーのランキング (`ac_rank`、`rated_point_sum_rank`、`streak_rank` など) 上の値と順位を取得します。
+pub async fn fetch_user_rank(ranking: &str, user: &str) -> Result<UserRankItem, Error> {
+    let url = Url::parse_with_params(
+        &kenkoooo_url(&format!("/atcoder-api/v3/user/{}", ranking)),
+        &[("user", user)],
+    )?;
+    http_get(url.as_str()).await
+}
+
+/// AtCoderの提出ページから、提出した問題の問題IDを取得します。
+/// 提出ページに問題へのリンクが見つからない場合は `None` を返します。
+pub async fn fetch_submission_problem_id(
+    contest_id: &str,
+    submission_id: &str,
+) -> Result<Option<String>, Error> {
+    let mut url = Url::parse("https://atcoder.jp/contests").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .push(contest_id)
+        .push("submissions")
+        .push(submission_id);
+    let page = client()
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let task_path = format!("/contests/{}/tasks/", contest_id);
+    Ok(page.split_once(&task_path).and_then(|(_, rest)| {
+        rest.split(['"', '?', '#', '/'])
+            .next()
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+    }))
+}
+
+/// AtCoderのユーザーのコンテスト成績表を取得します。
+pub async fn fetch_contest_history(user: &str) -> Result<Vec<ContestResult>, Error> {
+    let mut url = Url::parse("https://atcoder.jp/users").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .push(user)
+        .push("history")
+        .push("json");
+    http_get(url.as_str()).await
+}
+
+/// 予定されているコンテストが載っている、AtCoderのコンテスト一覧のページ
+pub const ATCODER_CONTESTS_URL: &str = "https://atcoder.jp/contests/";
+
+/// AtCoderのユーザーページのURLを返します。
+pub fn user_page_url(user: &str) -> String {
+    let mut url = Url::parse("https://atcoder.jp/users").unwrap();
+    url.path_segments_mut().unwrap().push(user);
+    url.into()
+}
+
+/// AtCoderのユーザーページから、正式な表記のユーザー名を取得します。
+/// 提出のないユーザーも見つけられるよう、提出ではなくユーザーページを使います。
+/// ユーザーが存在しない場合は `None` を返します。
+pub async fn canonical_user_name(
+    api: &dyn AtcoderApi,
+    user: &str,
+) -> Result<Option<String>, Error> {
+    if user.is_empty() || !user.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Ok(None);
+    }
+    let Some(page) = api.user_page(user).await? else {
+        return Ok(None);
+    };
+    Ok(Some(page_user_name(&page).unwrap_or(user).to_string()))
+}
+
+/// ユーザーページのタイトル (`tourist - AtCoder`) から、ユーザー名を取り出します。
+fn page_user_name(page: &str) -> Option<&str> {
+    let (_, rest) = page.split_once("<title>")?;
+    let (title, _) = rest.split_once("</title>")?;
+    title
+        .trim()
+        .strip_suffix(" - AtCoder")
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// 通知の作成に使うAtCoder ProblemsとAtCoderのAPI
+#[async_trait]
+pub trait AtcoderApi: Send + Sync {
+    async fn problems(&self) -> Result<Vec<ProblemItem>, Error>;
+    async fn problem_models(&self) -> Result<HashMap<String, ProblemModelItem>, Error>;
+    async fn contests(&self) -> Result<Vec<ContestItem>, Error>;
+    /// `from_second` 以降のユーザーの提出を最大500件取得します。
+    async fn user_submissions(
+        &self,
+        user: &str,
+        from_second: i64,
+    ) -> Result<Vec<SubmissionItem>, Error>;
+    /// AtCoderのユーザーページのHTMLを取得します。ユーザーが存在しない場合は `None` を返します。
+    async fn user_page(&self, user: &str) -> Result<Option<String>, Error>;
+    /// 予定されているコンテストが載っている、AtCoderのコンテスト一覧のページのHTMLを取得します。
+    async fn contests_page(&self) -> Result<String, Error>;
+}
+
+/// kenkoooo.com にリクエストを送る [`AtcoderApi`] の実装
+pub struct KenkooooApi;
+
+#[async_trait]
+impl AtcoderApi for KenkooooApi {
+    async fn problems(&self) -> Result<Vec<ProblemItem>, Error> {
+        http_get(&kenkoooo_url("/resources/problems.json")).await
+    }
+
+    async fn problem_models(&self) -> Result<HashMap<String, ProblemModelItem>, Error> {
+        http_get(&kenkoooo_url("/resources/problem-models.json")).await
+    }
+
+    async fn contests(&self) -> Result<Vec<ContestItem>, Error> {
+        http_get(&kenkoooo_url("/resources/contests.json")).await
+    }
+
+    async fn user_submissions(
+        &self,
+        user: &str,
+        from_second: i64,
+    ) -> Result<Vec<SubmissionItem>, Error> {
+        fetch_submissions(user, from_second).await
+    }
+
+    async fn user_page(&self, user: &str) -> Result<Option<String>, Error> {
+        metrics::API_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        let res = client().get(user_page_url(user)).send().await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(res.error_for_status()?.text().await?))
+    }
+
+    async fn contests_page(&self) -> Result<String, Error> {
+        metrics::API_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        let res = client().get(ATCODER_CONTESTS_URL).send().await?;
+        Ok(res.error_for_status()?.text().await?)
+    }
+}
+
+/// テスト用に、決まったデータを返す [`AtcoderApi`] の実装
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockApi {
+    pub problems: Vec<ProblemItem>,
+    pub problem_models: HashMap<String, ProblemModelItem>,
+    pub contests: Vec<ContestItem>,
+    pub submissions: Vec<SubmissionItem>,
+    /// ユーザー名ごとのユーザーページのHTML
+    pub user_pages: HashMap<String, String>,
+    /// `user_submissions` に渡された `(user, from_second)` の記録
+    pub submission_requests: Mutex<Vec<(String, i64)>>,
+    /// AtCoderのコンテスト一覧のページのHTML
+    pub contests_page: String,
+    /// 一時的な不具合を再現するため、`user_submissions` が最初に空の配列を返す回数
+    pub empty_submission_responses: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl AtcoderApi for MockApi {
+    async fn problems(&self) -> Result<Vec<ProblemItem>, Error> {
+        Ok(self.problems.clone())
+    }
+
+    async fn problem_models(&self) -> Result<HashMap<String, ProblemModelItem>, Error> {
+        Ok(self.problem_models.clone())
+    }
+
+    async fn contests(&self) -> Result<Vec<ContestItem>, Error> {
+        Ok(self.contests.clone())
+    }
+
+    /// 本物のAPIと同じく、提出時刻の順に最大500件を返します。
+    async fn user_submissions(
+        &self,
+        user: &str,
+        from_second: i64,
+    ) -> Result<Vec<SubmissionItem>, Error> {
+        self.submission_requests
+            .lock()
+            .unwrap()
+            .push((user.to_string(), from_second));
+        let empty = self.empty_submission_responses.fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |n| n.checked_sub(1),
+        );
+        if empty.is_ok() {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .submissions
+            .iter()
+            .filter(|s| s.user_id.eq_ignore_ascii_case(user) && s.epoch_second >= from_second)
+            .sorted_by_key(|s| s.epoch_second)
+            .take(SUBMISSIONS_PAGE_SIZE)
+            .cloned()
+            .collect())
+    }
+
+    async fn user_page(&self, user: &str) -> Result<Option<String>, Error> {
+        Ok(self
+            .user_pages
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(user))
+            .map(|(_, page)| page.clone()))
+    }
+
+    async fn contests_page(&self) -> Result<String, Error> {
+        Ok(self.contests_page.clone())
+    }
+}
+
+/// `from_second` 以降のユーザーの提出を、ページングしながらすべて取得します。
+pub async fn fetch_submission_history(
+    api: &dyn AtcoderApi,
+    user: &str,
+    from_second: i64,
+) -> Result<Vec<SubmissionItem>, Error> {
+    submission_history(api, user, from_second, confirm_empty_delay()).await
+}
+
+/// `from_second` 以降のユーザーの提出を、ページングしながらすべて取得します。
+/// `confirm_empty` を指定した場合、最初のページが空なら、その時間だけ待ってから一度だけ確かめます。
+async fn submission_history(
+    api: &dyn AtcoderApi,
+    user: &str,
+    from_second: i64,
+    confirm_empty: Option<std::time::Duration>,
+) -> Result<Vec<SubmissionItem>, Error> {
+    let mut history = vec![];
+    let mut from_second = from_second;
+    loop {
+        let mut page = api.user_submissions(user, from_second).await?;
+        // 一時的な不具合でも空の配列が返ることがあるため、最初のページが空なら一度だけ確かめる
+        if let Some(delay) = confirm_empty.filter(|_| history.is_empty() && page.is_empty()) {
+            sleep(delay).await;
+            page = api.user_submissions(user, from_second).await?;
+            if page.is_empty() {
+                println!("No submissions confirmed: {} (from {})", user, from_second);
+            } else {
+                println!(
+                    "Empty submissions on first try, got {} on retry: {}",
+                    page.len(),
+                    user
+                );
+            }
+        }
+        let len = page.len();
+        if let Some(last) = page.last() {
+            from_second = last.epoch_second + 1;
+        }
+        history.extend(page);
+        if len < SUBMISSIONS_PAGE_SIZE {
+            break;
+        }
+    }
+    Ok(history)
+}
+
+/// 問題一覧と難易度モデルを取得します。取得済みのものが新しければキャッシュを返します。
+pub async fn problem_data(api: &dyn AtcoderApi) -> Result<Arc<ProblemData>, Error> {
+    if let Some(data) = PROBLEM_DATA.lock().unwrap().as_ref() {
+        if Local::now() - data.fetched_at < PROBLEM_DATA_TTL {
+            return Ok(data.clone());
+        }
+    }
+
+    let data = Arc::new(ProblemData {
+        problems: api
+            .problems()
+            .await?
+            .into_iter()
+            .map(|p| (p.id.clone(), p))
+            .collect(),
+        problem_models: api.problem_models().await?,
+        contests: api
+            .contests()
+            .await?
+            .into_iter()
+            .map(|c| (c.id.clone(), c))
+            .collect(),
+        fetched_at: Local::now(),
+    });
+    if let Err(e) = save_problem_cache(&data) {
+        println!("Failed to save problem cache: {:?}", e);
+    }
+    PROBLEM_DATA.lock().unwrap().replace(data.clone());
+    Ok(data)
+}
+
+/// メモリ上の問題データを取得した時刻を返します。まだ取得していない場合は `None` を返します。
+pub fn problem_data_fetched_at() -> Option<DateTime<Local>> {
+    PROBLEM_DATA
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|data| data.fetched_at)
+}
+
+/// 問題データのキャッシュをメモリとディスクから消し、すぐに取得し直します。
+pub async fn refresh_problem_data(api: &dyn AtcoderApi) -> Result<Arc<ProblemData>, Error> {
+    PROBLEM_DATA.lock().unwrap().take();
+    match fs::remove_file(problem_cache_path()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            println!("Failed to remove problem cache: {:?}", e);
+        }
+        _ => {}
+    }
+    problem_data(api).await
+}
+
+/// ディスクに保存された問題データを読み込みます。
+/// キャッシュが古いか読み込めなかった場合は、バックグラウンドで再取得します。
+pub fn restore_problem_data() {
+    match load_problem_cache() {
+        Ok(data) => {
+            println!("Problem cache restored (fetched at {})", data.fetched_at);
+            let fresh = Local::now() - data.fetched_at < PROBLEM_DATA_TTL;
+            PROBLEM_DATA.lock().unwrap().replace(Arc::new(data));
+            if fresh {
+                return;
+            }
+        }
+        Err(e) => {
+            println!("Note: problem cache not available ({}), fetching", e);
+        }
+    }
+    tokio::spawn(async {
+        if let Err(e) = problem_data(&KenkooooApi).await {
+            println!("Failed to fetch problem data: {:?}", e);
+        }
+    });
+}
+
+/// 問題データのキャッシュを保存するファイルのパス。設定ファイルと同じディレクトリに置きます
+fn problem_cache_path() -> PathBuf {
+    PathBuf::from(config_path()).with_file_name(PROBLEM_CACHE_FILE)
+}
+
+fn save_problem_cache(data: &ProblemData) -> Result<(), Error> {
+    let data = serde_json::to_string(data)?;
+    fs::write(problem_cache_path(), data)?;
+    Ok(())
+}
+
+fn load_problem_cache() -> Result<ProblemData, Error> {
+    let data = fs::read_to_string(problem_cache_path())?;
+    let data = serde_json::from_str(&data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, mock_server};
+    use poise::futures_util::future::join_all;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn page_user_name_reads_canonical_case() {
+        let page = "<html><head>\n<title>tourist - AtCoder</title>\n</head></html>";
+        assert_eq!(page_user_name(page), Some("tourist"));
+        assert_eq!(page_user_name("<title>AtCoder</title>"), None);
+        assert_eq!(page_user_name("<html></html>"), None);
+    }
+
+    #[test]
+    fn non_json_response_is_a_retryable_parse_error() {
+        let url = "https://kenkoooo.com/atcoder/resources/problems.json";
+        let e = parse_response::<Vec<ProblemItem>>(url, "<html>メンテナンス中</html>").unwrap_err();
+        assert!(e.is::<serde_json::Error>());
+        assert!(is_retryable(&e));
+        assert_eq!(
+            e.to_string(),
+            format!("Failed to parse response from {}", url)
+        );
+    }
+
+    #[test]
+    fn user_names_are_encoded_in_urls() {
+        let url = submissions_url("a&b c", 0).unwrap();
+        assert_eq!(
+            url.as_str(),
+            format!(
+                "{}/atcoder-api/v3/user/submissions?user=a%26b+c&from_second=0",
+                mock_server::url()
+            )
+        );
+        assert_eq!(
+            url.query_pairs().collect::<Vec<_>>(),
+            [
+                ("user".into(), "a&b c".into()),
+                ("from_second".into(), "0".into())
+            ]
+        );
+        assert_eq!(
+            user_page_url("a b/c?d"),
+            "https://atcoder.jp/users/a%20b%2Fc%3Fd"
+        );
+    }
+
+    #[tokio::test]
+    async fn submission_history_follows_pages() {
+        let api = MockApi {
+            submissions: (0..1200)
+                .map(|i| fixtures::submission(i, "alice", "abc300_a", i, JudgeStatus::Wa))
+                .collect(),
+            ..Default::default()
+        };
+        let history = fetch_submission_history(&api, "alice", 0).await.unwrap();
+        assert!(history.iter().map(|s| s.id).eq(0..1200));
+        assert_eq!(
+            *api.submission_requests.lock().unwrap(),
+            [
+                ("alice".to_string(), 0),
+                ("alice".to_string(), 500),
+                ("alice".to_string(), 1000)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn canonical_user_name_reads_user_page() {
+        let api = MockApi {
+            user_pages: HashMap::from([(
+                "tourist".to_string(),
+                "<title>tourist - AtCoder</title>".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let name = |user| canonical_user_name(&api, user);
+        assert_eq!(name("TOURIST").await.unwrap().as_deref(), Some("tourist"));
+        assert_eq!(name("nobody").await.unwrap(), None);
+        assert_eq!(name("a b").await.unwrap(), None);
+        assert_eq!(name("").await.unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_submission_requests_are_spaced() {
+        let next_request = Mutex::new(None);
+        let interval = std::time::Duration::from_secs(1);
+        let start = Instant::now();
+        let turns = join_all((0..3).map(|_| async {
+            wait_submissions_turn(&next_request, interval).await;
+            Instant::now() - start
+        }))
+        .await;
+        assert_eq!(turns, [0, 1, 2].map(std::time::Duration::from_secs));
+
+        // 間隔より長く空いた後のリクエストは待たずに送ります
+        sleep(interval * 5).await;
+        let before = Instant::now();
+        wait_submissions_turn(&next_request, interval).await;
+        assert_eq!(Instant::now(), before);
+    }
+
+    #[test]
+    fn missing_difficulty_falls_back_to_series_median() {
+        let problem_data = fixtures::problem_data(
+            [
+                "abc300_d", "abc301_d", "abc302_d", "abc303_d", "arc100_d", "abc301_e",
+            ]
+            .into_iter()
+            .map(|id| fixtures::problem(id, id))
+            .collect(),
+            &[
+                ("abc301_d", 1200),
+                ("abc302_d", 1000),
+                ("abc303_d", 1400),
+                ("arc100_d", 2800),
+                ("abc301_e", 2000),
+            ],
+            vec![],
+        );
+        assert_eq!(problem_data.difficulty("abc302_d"), Some((1000, false)));
+        // abc300_d の難易度モデルはないので、ABCのD問題の中央値を推定値にします
+        assert_eq!(problem_data.difficulty("abc300_d"), Some((1200, true)));
+        // 問題一覧にもない問題は、難易度が分かりません
+        assert_eq!(problem_data.difficulty("abc999_z"), None);
+    }
+
+    /// `user` の、`epoch_seconds` の時刻の提出を返すsubmissions APIの応答
+    fn submissions_response(
+        user: &str,
+        epoch_seconds: std::ops::Range<i64>,
+    ) -> mock_server::Response {
+        mock_server::Response::json(
+            epoch_seconds
+                .map(|t| {
+                    serde_json::json!({
+                        "id": t,
+                        "epoch_second": t,
+                        "problem_id": "abc300_a",
+                        "contest_id": "abc300",
+                        "user_id": user,
+                        "language": "Rust (rustc 1.70.0)",
+                        "point": 100.0,
+                        "length": 1000,
+                        "result": "AC",
+                        "execution_time": 1,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn submissions_path(user: &str, from_second: i64) -> String {
+        format!(
+            "/atcoder-api/v3/user/submissions?user={}&from_second={}",
+            user, from_second
+        )
+    }
+
+    #[tokio::test]
+    async fn http_submission_history_follows_pages() {
+        let server = mock_server::server();
+        let user = "paging_user";
+        server.respond(
+            &submissions_path(user, 0),
+            [submissions_response(user, 0..500)],
+        );
+        server.respond(
+            &submissions_path(user, 500),
+            [submissions_response(user, 500..1000)],
+        );
+        server.respond(
+            &submissions_path(user, 1000),
+            [submissions_response(user, 1000..1001)],
+        );
+
+        let history = fetch_submission_history(&KenkooooApi, user, 0)
+            .await
+            .unwrap();
+        assert!(history.iter().map(|s| s.id).eq(0..1001));
+        assert_eq!(server.request_count(&submissions_path(user, 0)), 1);
+        assert_eq!(server.request_count(&submissions_path(user, 500)), 1);
+        assert_eq!(server.request_count(&submissions_path(user, 1000)), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_request_is_retried() {
+        let server = mock_server::server();
+        let user = "retry_user";
+        let path = submissions_path(user, 0);
+        server.respond(
+            &path,
+            [
+                mock_server::Response::too_many_requests(0),
+                submissions_response(user, 0..3),
+            ],
+        );
+
+        let submissions = fetch_submissions(user, 0).await.unwrap();
+        assert_eq!(submissions.len(), 3);
+        assert_eq!(server.request_count(&path), 2);
+    }
+
+    #[tokio::test]
+    async fn persistent_rate_limit_is_reported() {
+        let server = mock_server::server();
+        let user = "limited_user";
+        let path = submissions_path(user, 0);
+        server.respond(&path, [mock_server::Response::too_many_requests(0)]);
+
+        let e = fetch_submissions(user, 0).await.unwrap_err();
+        let limited = e.downcast_ref::<RateLimited>().unwrap();
+        assert_eq!(limited.retry_after, Some(std::time::Duration::ZERO));
+        assert_eq!(server.request_count(&path), 1 + RATE_LIMIT_RETRIES as usize);
+    }
+
+    #[test]
+    fn problem_indices_are_sorted_naturally() {
+        let sorted = ["Ex", "G", "B", "F2", "A", "F", "AA", "Z", "C"]
+            .into_iter()
+            .sorted_by_key(|index| problem_index_key(index))
+            .collect_vec();
+        // ABCの Ex はH問題の位置に、2文字の番号は1文字の番号の後に並べます
+        assert_eq!(sorted, ["A", "B", "C", "F", "F2", "G", "Ex", "Z", "AA"]);
+        assert_eq!(problem_index_key("Ex"), problem_index_key("H"));
+    }
+
+    #[test]
+    fn missing_problem_is_guessed_from_id() {
+        let problem = ProblemItem::guess("abc300_a");
+        assert_eq!(problem.contest_id, "abc300");
+        assert_eq!(problem.problem_index, "A");
+        assert_eq!(problem.title, "abc300_a");
+        // `_` のないIDは、IDそのものをコンテストIDとします
+        assert_eq!(ProblemItem::guess("practice").contest_id, "practice");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn empty_first_page_is_confirmed_when_enabled() {
+        let api = MockApi {
+            submissions: vec![fixtures::submission(
+                1,
+                "alice",
+                "abc300_a",
+                100,
+                JudgeStatus::Ac,
+            )],
+            empty_submission_responses: AtomicUsize::new(1),
+            ..Default::default()
+        };
+        let delay = std::time::Duration::from_secs(3);
+        let start = Instant::now();
+        let history = submission_history(&api, "alice", 0, Some(delay))
+            .await
+            .unwrap();
+        // 再取得で返った提出を使います
+        assert_eq!(history.len(), 1);
+        assert_eq!(Instant::now() - start, delay);
+        assert_eq!(api.submission_requests.lock().unwrap().len(), 2);
+
+        // 既定では確かめずに、空の結果をそのまま使います
+        api.empty_submission_responses.store(1, Ordering::Relaxed);
+        let history = submission_history(&api, "alice", 0, None).await.unwrap();
+        assert!(history.is_empty());
+        assert_eq!(api.submission_requests.lock().unwrap().len(), 3);
+    }
+}
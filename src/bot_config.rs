@@ -0,0 +1,173 @@
+use std::{fs, sync::OnceLock};
+
+use anyhow::{bail, Context as _, Error};
+use serde::Deserialize;
+
+/// 設定ファイルの既定のパス。環境変数 `BOT_CONFIG` で変更できます。
+const DEFAULT_BOT_CONFIG_PATH: &str = "bot.toml";
+
+/// 起動時の設定ファイル (`bot.toml`) の内容。
+/// それぞれの値は対応する環境変数の既定値になり、環境変数が設定されていればそちらを優先します。
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct BotConfig {
+    /// Discordのトークン (`DISCORD_TOKEN`)
+    token: Option<String>,
+    /// 設定を保存するファイル (`CONFIG_PATH`)
+    config_path: Option<String>,
+    /// AtCoder ProblemsのAPIのURL (`KENKOOOO_BASE_URL`)
+    kenkoooo_base_url: Option<String>,
+    /// submissions APIへのリクエストの間隔 (`SUBMISSIONS_INTERVAL_MS`)
+    submissions_interval_ms: Option<u64>,
+    /// 提出が1件も返らなかったときに、確かめるために再取得するまで待つ時間 (`CONFIRM_EMPTY_SUBMISSIONS_MS`)
+    confirm_empty_submissions_ms: Option<u64>,
+    /// 定期通知を行う時刻 (`DAILY_HOUR`)
+    daily_hour: Option<u32>,
+    /// メトリクスを公開するアドレス (`METRICS_ADDR`)
+    metrics_addr: Option<String>,
+    /// Botの管理者のユーザーID (`OWNER_ID`)
+    owner_id: Option<u64>,
+    /// ログの詳しさ (`LOG_LEVEL`)
+    log_level: Option<String>,
+}
+
+impl BotConfig {
+    /// 環境変数の名前と、ファイルに書かれた値の組
+    fn env_values(self) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("DISCORD_TOKEN", self.token),
+            ("CONFIG_PATH", self.config_path),
+            ("KENKOOOO_BASE_URL", self.kenkoooo_base_url),
+            (
+                "SUBMISSIONS_INTERVAL_MS",
+                self.submissions_interval_ms.map(|ms| ms.to_string()),
+            ),
+            (
+                "CONFIRM_EMPTY_SUBMISSIONS_MS",
+                self.confirm_empty_submissions_ms.map(|ms| ms.to_string()),
+            ),
+            ("DAILY_HOUR", self.daily_hour.map(|h| h.to_string())),
+            ("METRICS_ADDR", self.metrics_addr),
+            ("OWNER_ID", self.owner_id.map(|id| id.to_string())),
+            ("LOG_LEVEL", self.log_level),
+        ]
+    }
+}
+
+/// 設定ファイルを読み込み、環境変数に設定されていない値を環境変数に反映します。
+/// `BOT_CONFIG` を指定した場合はファイルが必須で、指定していない場合は `bot.toml` があれば読み込みます。
+/// 他のスレッドが環境変数を読む前の、起動直後に呼んでください。
+pub fn apply() -> Result<(), Error> {
+    let (path, required) = match std::env::var("BOT_CONFIG") {
+        Ok(path) => (path, true),
+        Err(_) => (DEFAULT_BOT_CONFIG_PATH.to_string(), false),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(Error::new(e).context(format!("Failed to read {}", path))),
+    };
+    let config =
+        toml::from_str::<BotConfig>(&text).with_context(|| format!("Failed to parse {}", path))?;
+    for (name, value) in config.env_values() {
+        if let (Err(_), Some(value)) = (std::env::var(name), value) {
+            std::env::set_var(name, value);
+        }
+    }
+    println!("Bot config loaded from {}", path);
+    Ok(())
+}
+
+/// ログの詳しさ
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    /// 通知の実行やエラーなど、通常のログだけを出力します
+    Info,
+    /// 外部APIへのリクエストや、ユーザーごとの処理の進み具合も出力します
+    Debug,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            _ => None,
+        }
+    }
+}
+
+/// ログの詳しさ。環境変数 `LOG_LEVEL` (`info` か `debug`) で変更でき、既定は `info` です。
+pub fn log_level() -> LogLevel {
+    static LEVEL: OnceLock<LogLevel> = OnceLock::new();
+    *LEVEL.get_or_init(|| {
+        std::env::var("LOG_LEVEL")
+            .ok()
+            .and_then(|level| LogLevel::parse(&level))
+            .unwrap_or(LogLevel::Info)
+    })
+}
+
+/// 起動に必要な値がそろっているかと、値が正しいかを確かめ、問題をすべて返します。
+/// `var` は環境変数の値を返す関数です。
+fn problems(var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let mut problems = vec![];
+    if var("DISCORD_TOKEN").is_none_or(|token| token.trim().is_empty()) {
+        problems.push("DISCORD_TOKEN (`token` in bot.toml) is not set".to_string());
+    }
+    if let Some(level) = var("LOG_LEVEL").filter(|level| LogLevel::parse(level).is_none()) {
+        problems.push(format!(
+            "LOG_LEVEL (`log_level` in bot.toml) must be `info` or `debug`, got `{}`",
+            level
+        ));
+    }
+    problems
+}
+
+/// 起動に必要な値を確かめ、Discordのトークンを返します。
+/// 足りない値や正しくない値があれば、それらをすべて並べたエラーを返します。
+pub fn validate() -> Result<String, Error> {
+    let problems = problems(|name| std::env::var(name).ok());
+    if !problems.is_empty() {
+        bail!(
+            "Invalid bot config:\n{}",
+            problems
+                .iter()
+                .map(|problem| format!("  - {}", problem))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(std::env::var("DISCORD_TOKEN")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn problems_with(vars: &[(&str, &str)]) -> Vec<String> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        problems(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn all_problems_are_reported_together() {
+        let problems = problems_with(&[("LOG_LEVEL", "verbose")]);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("DISCORD_TOKEN"));
+        assert!(problems[1].contains("`verbose`"));
+
+        assert!(problems_with(&[("DISCORD_TOKEN", "token"), ("LOG_LEVEL", "DEBUG")]).is_empty());
+        assert!(problems_with(&[("DISCORD_TOKEN", "token")]).is_empty());
+        assert_eq!(problems_with(&[("DISCORD_TOKEN", " ")]).len(), 1);
+    }
+
+    #[test]
+    fn log_level_is_read_from_file() {
+        let config = toml::from_str::<BotConfig>("token = \"abc\"\nlog_level = \"debug\"").unwrap();
+        let values = config.env_values();
+        assert!(values.contains(&("LOG_LEVEL", Some("debug".into()))));
+        assert!(values.contains(&("DISCORD_TOKEN", Some("abc".into()))));
+    }
+}
@@ -1,42 +1,212 @@
-use crate::{notify, save, Context};
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use crate::{
+    activity, api, backup_path, compare, contest_reminder, contests, daily_job, difficulty, help,
+    languages, messages, notify, paginate::paginate, parse_config, problem, random, rating, save,
+    stats, streak, suggest, virtual_contest, Config, Context, Data,
+};
 use anyhow::Error;
-use itertools::Itertools;
-use poise::serenity_prelude as serenity;
-use serenity::Mentionable;
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use poise::{futures_util::future::join_all, serenity_prelude as serenity, CreateReply};
+use serenity::{
+    collector::ComponentInteractionCollector, CreateActionRow, CreateAttachment, CreateButton,
+    CreateEmbed, CreateInteractionResponse, Mentionable,
+};
+
+/// 確認のボタンが押されるのを待つ時間
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// APIへのリクエストが多いコマンドを、同じサーバーで再び実行できるまでの時間
+pub const FETCH_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// コマンドに、サーバーごとに [`FETCH_COOLDOWN`] のクールダウンを設定します。
+pub fn with_fetch_cooldown(
+    command: poise::Command<Arc<Data>, Error>,
+) -> poise::Command<Arc<Data>, Error> {
+    command.cooldown_config.write().unwrap().guild = Some(FETCH_COOLDOWN);
+    command
+}
+
+/// コマンドを `subcommands` をまとめたグループにします。
+/// サブコマンドの権限は `default_member_permissions` では制限されないため、
+/// 管理者向けのコマンドには `required_permissions` も指定してください。
+pub fn group(
+    mut command: poise::Command<Arc<Data>, Error>,
+    subcommands: Vec<poise::Command<Arc<Data>, Error>>,
+) -> poise::Command<Arc<Data>, Error> {
+    command.subcommands = subcommands;
+    command.subcommand_required = true;
+    command
+}
+
+/// 通知の設定やユーザーの登録を行います。
+#[poise::command(slash_command)]
+pub async fn config(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// 通知するユーザーの登録や、Discordアカウントとの紐付けを行います。
+#[poise::command(slash_command, rename = "user")]
+pub async fn config_user(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// 設定の書き出しや初期化など、管理者向けの操作を行います。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn admin(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// ACした問題の通知や、問題の情報を表示します。
+#[poise::command(slash_command)]
+pub async fn report(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// ユーザーごとの成績を表示します。
+#[poise::command(slash_command)]
+pub async fn user(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
 
 /// メッセージを送信するチャンネルを設定します。
 #[poise::command(slash_command)]
 pub async fn channel(ctx: Context<'_>) -> Result<(), Error> {
-    {
-        ctx.data().channel.lock().unwrap().replace(ctx.channel_id());
+    let previous = {
+        let previous = ctx.data().channel.lock().unwrap().replace(ctx.channel_id());
+        *ctx.data().guild.lock().unwrap() = ctx.guild_id();
         save(ctx.data())?;
-    }
-    ctx.reply(format!(
-        "チャンネルを {} に設定しました。",
-        ctx.channel_id().mention()
-    ))
-    .await?;
-    println!("Channel set: {:?}", ctx.channel_id());
+        previous
+    };
+    ctx.reply(messages::channel_set(ctx.channel_id(), previous))
+        .await?;
+    println!(
+        "Channel set: {:?} (previous: {:?})",
+        ctx.channel_id(),
+        previous
+    );
     Ok(())
 }
 
-/// AtCoderのユーザーを登録します。カンマ区切りで複数人指定できます。
+/// AtCoderのユーザーを登録します。カンマか空白区切りで複数人指定可
 #[poise::command(slash_command)]
 pub async fn register(
     ctx: Context<'_>,
     #[description = "AtCoderのユーザー名"] users: String,
 ) -> Result<(), Error> {
-    let users = users
-        .split(",")
-        .map(|u| u.trim().to_string())
+    let users = split_users(&users);
+    ctx.defer().await?;
+    let mut added = vec![];
+    let mut already = vec![];
+    let mut not_found = vec![];
+    let mut failed = vec![];
+    for user in users {
+        if let Some(registered) = find_user(ctx.data(), &user).pop() {
+            already.push(registered);
+            continue;
+        }
+        // 1人の確認に失敗しても、他のユーザーの登録は続けます
+        match api::canonical_user_name(&api::KenkooooApi, &user).await {
+            Ok(Some(canonical)) => {
+                ctx.data().users.lock().unwrap().insert(canonical.clone());
+                added.push(canonical);
+            }
+            Ok(None) => not_found.push(user),
+            Err(e) => {
+                println!("Failed to look up user {}: {:?}", user, e);
+                failed.push(user);
+            }
+        }
+    }
+    save(ctx.data())?;
+    let mut message = messages::registered(&added, &already);
+    if !failed.is_empty() {
+        message.push(messages::user_lookup_failed(&failed));
+    }
+    let known = ctx
+        .data()
+        .users
+        .lock()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    for user in not_found {
+        let suggestion = suggest::suggest_user(&user, &known).await;
+        message.push(messages::user_not_found(&user, suggestion.as_deref()));
+    }
+    ctx.reply(message.join("\n")).await?;
+    println!("User registered: {:?}", &added);
+    Ok(())
+}
+
+/// 登録するユーザーを、指定したユーザーだけに置き換えます。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn setusers(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名 (カンマか空白区切り)"] users: String,
+) -> Result<(), Error> {
+    let users = split_users(&users);
+    ctx.defer().await?;
+    let mut new_users = BTreeSet::new();
+    let mut not_found = vec![];
+    for user in users {
+        if let Some(registered) = find_user(ctx.data(), &user).pop() {
+            new_users.insert(registered);
+            continue;
+        }
+        match api::canonical_user_name(&api::KenkooooApi, &user).await? {
+            Some(canonical) => {
+                new_users.insert(canonical);
+            }
+            None => not_found.push(user),
+        }
+    }
+    let old_users = ctx.data().users.lock().unwrap().clone();
+    let added = new_users
+        .difference(&old_users)
+        .cloned()
+        .collect::<Vec<_>>();
+    let removed = old_users
+        .difference(&new_users)
+        .cloned()
         .collect::<Vec<_>>();
     {
-        ctx.data().users.lock().unwrap().extend(users.clone());
+        for user in &removed {
+            ctx.data().links.lock().unwrap().remove(user);
+            ctx.data().display_names.lock().unwrap().remove(user);
+        }
+        *ctx.data().users.lock().unwrap() = new_users;
         save(ctx.data())?;
     }
-    ctx.reply(format!("ユーザー ({}) を登録しました。", users.join(", ")))
-        .await?;
-    println!("User registered: {:?}", &users);
+    let mut message = vec![format!(
+        "{}人追加、{}人削除しました。",
+        added.len(),
+        removed.len()
+    )];
+    if !added.is_empty() {
+        message.push(format!("追加: {}", added.join(", ")));
+    }
+    if !removed.is_empty() {
+        message.push(format!("削除: {}", removed.join(", ")));
+    }
+    if !not_found.is_empty() {
+        message.push(format!(
+            "ユーザー ({}) は見つかりませんでした。",
+            not_found.join(", ")
+        ));
+    }
+    ctx.reply(message.join("\n")).await?;
+    println!("Users set: added {:?}, removed {:?}", &added, &removed);
     Ok(())
 }
 
@@ -46,33 +216,2124 @@ pub async fn unregister(
     ctx: Context<'_>,
     #[description = "AtCoderのユーザー名"] user: String,
 ) -> Result<(), Error> {
+    let registered = find_user(ctx.data(), &user);
+    if registered.is_empty() {
+        ctx.reply(messages::user_not_registered(&user)).await?;
+        return Ok(());
+    }
     {
-        ctx.data().users.lock().unwrap().remove(&user);
+        for user in &registered {
+            ctx.data().users.lock().unwrap().remove(user);
+            ctx.data().links.lock().unwrap().remove(user);
+            ctx.data().display_names.lock().unwrap().remove(user);
+        }
         save(ctx.data())?;
     }
-    ctx.reply(format!("ユーザー ({}) を登録解除しました。", user))
-        .await?;
+    ctx.reply(messages::unregistered(&registered)).await?;
     println!("User unregistered: {:?}", &user);
     Ok(())
 }
 
+/// 通知に表示する名前を設定します。省略でユーザー名に戻します。
+#[poise::command(slash_command)]
+pub async fn setname(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+    #[description = "表示する名前"] name: Option<String>,
+) -> Result<(), Error> {
+    let Some(user) = find_user(ctx.data(), &user).pop() else {
+        ctx.reply(messages::user_not_registered(&user)).await?;
+        return Ok(());
+    };
+    // 管理者か、そのユーザーに紐付けられたDiscordアカウントだけが変更できます
+    let linked = ctx.data().links.lock().unwrap().get(&user).copied();
+    if !is_admin(ctx).await && linked != Some(ctx.author().id) {
+        ctx.reply(format!(
+            "ユーザー ({}) の名前は、管理者か /config user link で紐付けた本人だけが変更できます。",
+            user
+        ))
+        .await?;
+        return Ok(());
+    }
+    let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    {
+        let mut names = ctx.data().display_names.lock().unwrap();
+        match &name {
+            Some(name) => names.insert(user.clone(), name.clone()),
+            None => names.remove(&user),
+        };
+    }
+    save(ctx.data())?;
+    ctx.reply(match &name {
+        Some(name) => format!("ユーザー ({}) の表示名を {} に設定しました。", user, name),
+        None => format!("ユーザー ({}) の表示名を元に戻しました。", user),
+    })
+    .await?;
+    println!("Display name set: {:?} {:?}", user, name);
+    Ok(())
+}
+
+/// AtCoderのユーザーをDiscordアカウントに紐付け、通知でメンションします。
+#[poise::command(slash_command)]
+pub async fn link(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+    #[description = "紐付けるDiscordアカウント (省略時は自分)"] member: Option<serenity::User>,
+) -> Result<(), Error> {
+    let author = ctx.author().id;
+    let target = member.map_or(author, |member| member.id);
+    let admin = is_admin(ctx).await;
+    if target != author && !admin {
+        ctx.reply("他のDiscordアカウントへの紐付けは、管理者だけが行えます。")
+            .await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+    // 登録されていないユーザーは、見つかれば登録してから紐付けます
+    let (user, newly_registered) = match find_user(ctx.data(), &user).pop() {
+        Some(registered) => (registered, false),
+        None => match api::canonical_user_name(&api::KenkooooApi, user.trim()).await? {
+            Some(canonical) => (canonical, true),
+            None => {
+                ctx.reply(messages::user_not_found(user.trim(), None))
+                    .await?;
+                return Ok(());
+            }
+        },
+    };
+    let linked = ctx.data().links.lock().unwrap().get(&user).copied();
+    if linked.is_some_and(|id| id != target) && !admin {
+        ctx.reply(format!(
+            "ユーザー ({}) は他のDiscordアカウントに紐付けられています。",
+            user
+        ))
+        .await?;
+        return Ok(());
+    }
+    {
+        if newly_registered {
+            ctx.data().users.lock().unwrap().insert(user.clone());
+        }
+        ctx.data()
+            .links
+            .lock()
+            .unwrap()
+            .insert(user.clone(), target);
+        save(ctx.data())?;
+    }
+    let mut message = vec![];
+    if newly_registered {
+        message.push(messages::registered(std::slice::from_ref(&user), &[]).join("\n"));
+    }
+    message.push(format!(
+        "ユーザー ({}) を {} に紐付けました。通知でメンションします。",
+        user,
+        target.mention()
+    ));
+    ctx.reply(message.join("\n")).await?;
+    println!("User linked: {:?} {:?}", &user, target);
+    Ok(())
+}
+
+/// AtCoderのユーザーとDiscordアカウントの紐付けを解除します。
+#[poise::command(slash_command)]
+pub async fn unlink(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    let Some(user) = find_user(ctx.data(), &user).pop() else {
+        ctx.reply(messages::user_not_registered(&user)).await?;
+        return Ok(());
+    };
+    let Some(linked) = ctx.data().links.lock().unwrap().get(&user).copied() else {
+        ctx.reply(format!(
+            "ユーザー ({}) はDiscordアカウントに紐付けられていません。",
+            user
+        ))
+        .await?;
+        return Ok(());
+    };
+    if linked != ctx.author().id && !is_admin(ctx).await {
+        ctx.reply(format!(
+            "ユーザー ({}) の紐付けは、管理者か紐付けた本人だけが解除できます。",
+            user
+        ))
+        .await?;
+        return Ok(());
+    }
+    {
+        ctx.data().links.lock().unwrap().remove(&user);
+        save(ctx.data())?;
+    }
+    ctx.reply(format!("ユーザー ({}) の紐付けを解除しました。", user))
+        .await?;
+    println!("User unlinked: {:?} {:?}", &user, linked);
+    Ok(())
+}
+
+/// コマンドの実行者がサーバーの管理者かどうかを返します。
+async fn is_admin(ctx: Context<'_>) -> bool {
+    ctx.author_member()
+        .await
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.administrator())
+}
+
 /// 登録されているユーザーの一覧を表示します。
 #[poise::command(slash_command)]
 pub async fn registerlist(ctx: Context<'_>) -> Result<(), Error> {
-    let users = ctx.data().users.lock().unwrap().clone();
-    ctx.reply(format!(
-        "登録されているユーザー: {}",
-        users.iter().join(", ")
-    ))
-    .await?;
+    let users = ctx
+        .data()
+        .users
+        .lock()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    if users.is_empty() {
+        ctx.reply(messages::no_users()).await?;
+        return Ok(());
+    }
+    let title = messages::registered_list_title(users.len());
+    let pages = users
+        .chunks(20)
+        .map(|users| {
+            CreateEmbed::default()
+                .title(&title)
+                .description(users.join("\n"))
+        })
+        .collect::<Vec<_>>();
+    paginate(ctx, &pages).await?;
     Ok(())
 }
 
 /// 手動で実行します。
 #[poise::command(slash_command)]
-pub async fn run(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn run(
+    ctx: Context<'_>,
+    #[description = "チャンネルに投稿せず、結果をプレビューする"] preview: Option<bool>,
+    #[description = "遡る日数 (既定値: 1)"]
+    #[min = 1]
+    #[max = 7]
+    days: Option<u32>,
+) -> Result<(), Error> {
+    let days = days.unwrap_or(1).clamp(1, notify::MAX_REPORT_DAYS);
+    ctx.defer().await?;
+    if preview.unwrap_or(false) {
+        let report = notify::build_report(&api::KenkooooApi, ctx.data(), days).await?;
+        reply_report(ctx, &report).await?;
+        return Ok(());
+    }
+    let result = notify::notify(ctx.serenity_context().clone(), days).await;
+    daily_job::record_outcome(result.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+    result?;
+    // 一時停止中でも手動の実行は行い、停止中であることを伝えます
+    if *ctx.data().paused.lock().unwrap() {
+        let since = *ctx.data().paused_since.lock().unwrap();
+        ctx.reply(format!(
+            "完了！\n(定期通知は{}一時停止中です。/config resume で再開できます)",
+            paused_for(since)
+        ))
+        .await?;
+    } else {
+        ctx.reply("完了！").await?;
+    }
+    Ok(())
+}
+
+/// 指定したユーザーが今日ACした問題を表示します。登録は不要です。
+#[poise::command(slash_command)]
+pub async fn solvedtoday(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let Some(user) = api::canonical_user_name(&api::KenkooooApi, user.trim()).await? else {
+        ctx.reply(format!(
+            "ユーザー ({}) は見つかりませんでした。",
+            user.trim()
+        ))
+        .await?;
+        return Ok(());
+    };
+    let report =
+        notify::build_users_report(&api::KenkooooApi, ctx.data(), [user], notify::Period::Today)
+            .await?;
+    reply_report(ctx, &report).await?;
+    Ok(())
+}
+
+/// 通知の内容を、チャンネルに投稿せずにコマンドの返信として表示します。
+async fn reply_report(ctx: Context<'_>, report: &notify::Report) -> Result<(), Error> {
+    if report.is_empty() {
+        ctx.reply(report.empty_message()).await?;
+    } else if !report.user_lines.is_empty() {
+        for message in notify::chunk_lines(&report.lines()) {
+            ctx.send(
+                CreateReply::default()
+                    .content(message)
+                    .allowed_mentions(notify::allowed_mentions(None)),
+            )
+            .await?;
+        }
+    } else {
+        paginate(ctx, &report.embeds()).await?;
+    }
+    Ok(())
+}
+
+/// 昨日ACされた問題を、難易度の高い順に表示します。
+#[poise::command(slash_command)]
+pub async fn topdifficulty(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer().await?;
+    match notify::top_difficulty_embed(&api::KenkooooApi, ctx.data()).await? {
+        Some(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        None => {
+            ctx.reply("昨日は難易度の分かる問題を誰もACしませんでした。")
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// 初めてACした問題のみを通知するかどうかを設定します。
+#[poise::command(slash_command)]
+pub async fn firstaconly(
+    ctx: Context<'_>,
+    #[description = "初ACのみ通知する場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    {
+        *ctx.data().first_ac_only.lock().unwrap() = enabled;
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        "初めてACした問題のみを通知するように設定しました。"
+    } else {
+        "すべてのACを通知するように設定しました。"
+    })
+    .await?;
+    println!("First AC only set: {:?}", enabled);
+    Ok(())
+}
+
+/// ACできなかった問題も「挑戦」として通知するかどうかを設定します。
+#[poise::command(slash_command)]
+pub async fn attempts(
+    ctx: Context<'_>,
+    #[description = "挑戦した問題も通知する場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    {
+        *ctx.data().show_attempts.lock().unwrap() = enabled;
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        "ACできなかった問題も挑戦として通知するように設定しました。"
+    } else {
+        "ACした問題のみを通知するように設定しました。"
+    })
+    .await?;
+    println!("Show attempts set: {:?}", enabled);
+    Ok(())
+}
+
+/// 指定した言語の提出だけを通知します。省略で全言語に戻します。
+#[poise::command(slash_command)]
+pub async fn langfilter(
+    ctx: Context<'_>,
+    #[description = "言語名の一部 (Rust など)"] language: Option<String>,
+) -> Result<(), Error> {
+    let language = language
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty());
+    {
+        *ctx.data().language_filter.lock().unwrap() = language.clone();
+        save(ctx.data())?;
+    }
+    ctx.reply(match &language {
+        Some(language) => format!(
+            "言語に「{}」を含む提出のみを通知するように設定しました。",
+            language
+        ),
+        None => "すべての言語の提出を通知するように設定しました。".into(),
+    })
+    .await?;
+    println!("Language filter set: {:?}", language);
+    Ok(())
+}
+
+/// 問題の難易度を表示します。表示する内容は `/problem` と同じです。
+#[poise::command(slash_command)]
+pub async fn difficulty(
+    ctx: Context<'_>,
+    #[description = "問題ID (abc300_a など)、問題か提出のURL"] problem: String,
+) -> Result<(), Error> {
+    show_problem(ctx, &problem).await
+}
+
+/// 問題のタイトル・コンテスト・難易度を表示します。
+#[poise::command(slash_command)]
+pub async fn problem(
+    ctx: Context<'_>,
+    #[description = "問題ID (abc300_a など)、問題か提出のURL"] problem: String,
+) -> Result<(), Error> {
+    show_problem(ctx, &problem).await
+}
+
+/// `/problem` と `/difficulty` の本体。問題ID・問題のURL・提出のURLから問題を探して、埋め込みで表示します。
+async fn show_problem(ctx: Context<'_>, problem: &str) -> Result<(), Error> {
+    ctx.defer().await?;
+    let problem_id = match parse_submission_url(problem) {
+        Some((contest_id, submission_id)) => {
+            let problem_id = api::fetch_submission_problem_id(&contest_id, &submission_id)
+                .await
+                .unwrap_or_else(|e| {
+                    println!("Failed to fetch submission {}: {:?}", submission_id, e);
+                    None
+                });
+            let Some(problem_id) = problem_id else {
+                ctx.reply(format!(
+                    "提出 ({}) の問題が見つかりませんでした。",
+                    submission_id
+                ))
+                .await?;
+                return Ok(());
+            };
+            problem_id
+        }
+        None => parse_problem_id(problem),
+    };
+    let problem_data = api::problem_data(&api::KenkooooApi).await?;
+    let color_bands = *ctx.data().color_bands.lock().unwrap();
+    let embed = problem::problem_embed(&problem_data, &problem_id, color_bands);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// 条件に合う問題をランダムに1つ選びます。
+#[poise::command(slash_command)]
+pub async fn random(
+    ctx: Context<'_>,
+    #[description = "最低の難易度"] min_difficulty: Option<u32>,
+    #[description = "最高の難易度"] max_difficulty: Option<u32>,
+    #[description = "コンテストの種類"] contest: Option<random::ContestKind>,
+    #[description = "このユーザーが未ACの問題から選ぶ"] unsolved_by: Option<String>,
+) -> Result<(), Error> {
+    let unsolved_by = match unsolved_by {
+        Some(user) => match find_user(ctx.data(), &user).pop() {
+            Some(registered) => Some(registered),
+            None => {
+                ctx.reply(messages::user_not_registered(&user)).await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
     ctx.defer().await?;
-    notify::notify(ctx.serenity_context().clone()).await?;
-    ctx.reply("完了！").await?;
+    let color_bands = *ctx.data().color_bands.lock().unwrap();
+    match random::random_embed(
+        &api::KenkooooApi,
+        min_difficulty,
+        max_difficulty,
+        contest,
+        unsolved_by.as_deref(),
+        color_bands,
+    )
+    .await?
+    {
+        Some(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        None => {
+            ctx.reply("条件に合う問題が見つかりませんでした。").await?;
+        }
+    }
+    Ok(())
+}
+
+/// 自分に紐付けたユーザーのACをDMでも受け取るかどうかを設定します。
+#[poise::command(slash_command)]
+pub async fn dm(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+    #[description = "DMで受け取る場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    let author = ctx.author().id;
+    let Some(user) = find_user(ctx.data(), &user).pop() else {
+        ctx.reply(messages::user_not_registered(&user)).await?;
+        return Ok(());
+    };
+    let linked = ctx.data().links.lock().unwrap().get(&user).copied();
+    if linked != Some(author) {
+        ctx.reply(format!(
+            "ユーザー ({}) はあなたに紐付けられていません。先に /config user link で紐付けてください。",
+            user
+        ))
+        .await?;
+        return Ok(());
+    }
+    // DMの設定はDiscordアカウントごとなので、同じアカウントに紐付けたすべてのユーザーに適用されます
+    {
+        if enabled {
+            ctx.data().dm.lock().unwrap().insert(author);
+        } else {
+            ctx.data().dm.lock().unwrap().remove(&author);
+        }
+        save(ctx.data())?;
+    }
+    let users = ctx
+        .data()
+        .links
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, &id)| id == author)
+        .map(|(user, _)| user.clone())
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!("DM set: {:?} {:?}", author, enabled);
+    ctx.reply(if enabled {
+        format!("ユーザー ({}) のACをDMでも通知します。", users)
+    } else {
+        format!("ユーザー ({}) のACのDM通知を停止しました。", users)
+    })
+    .await?;
+    Ok(())
+}
+
+/// チャンネル・ユーザー・設定をすべて削除します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn reset(ctx: Context<'_>) -> Result<(), Error> {
+    {
+        ctx.data().restore(Data::default());
+        save(ctx.data())?;
+    }
+    ctx.reply("設定をすべて削除しました。").await?;
+    println!("Config reset");
+    Ok(())
+}
+
+/// 埋め込みの色を設定します。省略すると難易度の色を使います。
+#[poise::command(slash_command)]
+pub async fn embedcolor(
+    ctx: Context<'_>,
+    #[description = "固定の色 (#ff8000 など)"] color: Option<String>,
+) -> Result<(), Error> {
+    let embed_color = match color {
+        Some(color) => {
+            let hex = color.trim().trim_start_matches('#');
+            match u32::from_str_radix(hex, 16) {
+                Ok(color) if hex.len() == 6 => difficulty::EmbedColor::Fixed(color),
+                _ => {
+                    ctx.reply(format!(
+                        "色 ({}) は不正です。#ff8000 のように指定してください。",
+                        color
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+            }
+        }
+        None => difficulty::EmbedColor::Difficulty,
+    };
+    {
+        *ctx.data().embed_color.lock().unwrap() = embed_color;
+        save(ctx.data())?;
+    }
+    ctx.reply(match embed_color {
+        difficulty::EmbedColor::Difficulty => "埋め込みの色を難易度の色に設定しました。".into(),
+        difficulty::EmbedColor::Fixed(color) => {
+            format!("埋め込みの色を #{:06x} に設定しました。", color)
+        }
+    })
+    .await?;
+    println!("Embed color set: {:?}", embed_color);
+    Ok(())
+}
+
+/// 設定をJSONファイルとして出力します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn export(ctx: Context<'_>) -> Result<(), Error> {
+    let data = serde_json::to_string_pretty(&Config::new(ctx.data()))?;
+    ctx.send(
+        CreateReply::default()
+            .content("現在の設定です。")
+            .attachment(CreateAttachment::bytes(data, "config.json"))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// JSONファイルから設定を読み込みます。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "/admin export で出力したJSONファイル"] file: serenity::Attachment,
+) -> Result<(), Error> {
+    let data = match serde_json::from_slice(&file.download().await?)
+        .map_err(Error::new)
+        .and_then(parse_config)
+    {
+        Ok(data) => data,
+        Err(e) => {
+            ctx.reply(format!("設定ファイルを読み込めませんでした: {}", e))
+                .await?;
+            return Ok(());
+        }
+    };
+    let summary = format!(
+        "現在の設定を、読み込んだ設定 (ユーザー {} 人) で置き換えます。よろしいですか？",
+        data.users.lock().unwrap().len()
+    );
+    let (confirmed, reply) = confirm(ctx, &summary, "置き換える").await?;
+    if !confirmed {
+        reply
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .content("設定の読み込みを取りやめました。")
+                    .components(vec![]),
+            )
+            .await?;
+        return Ok(());
+    }
+    {
+        ctx.data().restore(data);
+        save(ctx.data())?;
+    }
+    reply
+        .edit(
+            ctx,
+            CreateReply::default()
+                .content("設定を読み込みました。")
+                .components(vec![]),
+        )
+        .await?;
+    println!("Config imported:");
+    println!("{:#?}", ctx.data());
+    Ok(())
+}
+
+/// Bot全体の設定をバックアップし、DMで送ります。(Botの管理者用)
+#[poise::command(slash_command, owners_only)]
+pub async fn backup(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let data = crate::backup(ctx.data())?;
+    let path = backup_path();
+    std::fs::write(&path, &data)?;
+    println!("Config backed up: {}", path);
+    let channel = ctx.author().create_dm_channel(ctx).await?;
+    channel
+        .send_message(
+            ctx,
+            notify::new_message(None)
+                .content(format!("設定のバックアップです。({})", path))
+                .add_file(CreateAttachment::bytes(data, "backup.json")),
+        )
+        .await?;
+    ctx.reply("設定をバックアップし、DMで送信しました。")
+        .await?;
+    Ok(())
+}
+
+/// 通知を埋め込みで送るか、テキストで送るかを設定します。
+#[poise::command(slash_command)]
+pub async fn postformat(
+    ctx: Context<'_>,
+    #[description = "通知の形式"] format: notify::PostFormat,
+) -> Result<(), Error> {
+    {
+        *ctx.data().post_format.lock().unwrap() = format;
+        save(ctx.data())?;
+    }
+    ctx.reply(match format {
+        notify::PostFormat::Embed => "通知を埋め込みで送るように設定しました。",
+        notify::PostFormat::Text => "通知をテキストで送るように設定しました。",
+    })
+    .await?;
+    println!("Post format set: {:?}", format);
+    Ok(())
+}
+
+/// 誰もACしなかった日にユーザーへ呼びかけるかを設定します。
+#[poise::command(slash_command)]
+pub async fn nudge(
+    ctx: Context<'_>,
+    #[description = "呼びかける場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    {
+        *ctx.data().nudge_idle.lock().unwrap() = enabled;
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        "誰もACしなかった日は、登録されているユーザーに呼びかけるように設定しました。"
+    } else {
+        "誰もACしなかった日の呼びかけを停止しました。"
+    })
+    .await?;
+    println!("Nudge idle set: {:?}", enabled);
+    Ok(())
+}
+
+/// 通知時にメンションするロールを設定します。省略すると解除します。
+#[poise::command(slash_command)]
+pub async fn setrole(
+    ctx: Context<'_>,
+    #[description = "メンションするロール"] role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    {
+        *ctx.data().role.lock().unwrap() = role.as_ref().map(|r| r.id);
+        save(ctx.data())?;
+    }
+    match &role {
+        Some(role) => {
+            ctx.reply(format!(
+                "通知時に {} をメンションするように設定しました。",
+                role.name
+            ))
+            .await?
+        }
+        None => ctx.reply("通知時のメンションを解除しました。").await?,
+    };
+    println!("Role set: {:?}", role.map(|r| r.id));
     Ok(())
 }
+
+/// 通知する問題をコンテストごとにまとめるかどうかを設定します。
+#[poise::command(slash_command)]
+pub async fn groupbycontest(
+    ctx: Context<'_>,
+    #[description = "コンテストごとにまとめる場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    {
+        *ctx.data().group_by_contest.lock().unwrap() = enabled;
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        "問題をコンテストごとにまとめて通知するように設定しました。"
+    } else {
+        "問題をまとめずに通知するように設定しました。"
+    })
+    .await?;
+    println!("Group by contest set: {:?}", enabled);
+    Ok(())
+}
+
+/// バーチャルコンテストの結果を表示します。
+#[poise::command(slash_command)]
+pub async fn vcresult(
+    ctx: Context<'_>,
+    #[description = "バーチャルコンテストのID"] id: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    match virtual_contest::standings_embeds(id.trim()).await {
+        Ok(pages) => paginate(ctx, &pages).await?,
+        Err(e)
+            if e.downcast_ref::<reqwest::Error>()
+                .and_then(|e| e.status())
+                .is_some_and(|s| s.is_client_error()) =>
+        {
+            ctx.reply(format!(
+                "バーチャルコンテスト ({}) が見つかりませんでした。",
+                id
+            ))
+            .await?;
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// 2人のAtCoderユーザーの成績を比較します。
+#[poise::command(slash_command)]
+pub async fn compare(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user_a: String,
+    #[description = "AtCoderのユーザー名"] user_b: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let color_bands = *ctx.data().color_bands.lock().unwrap();
+    match compare::compare_embed(
+        &api::KenkooooApi,
+        user_a.trim(),
+        user_b.trim(),
+        ctx.data().timezone(),
+        color_bands,
+    )
+    .await
+    {
+        Ok(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        Err(e) => match e.downcast::<rating::UserNotFound>() {
+            Ok(rating::UserNotFound(user)) => {
+                ctx.reply(format!("ユーザー ({}) は見つかりませんでした。", user))
+                    .await?;
+            }
+            Err(e) => return Err(e),
+        },
+    }
+    Ok(())
+}
+
+/// AtCoderのユーザーのAC数や連続AC日数などの統計を表示します。
+#[poise::command(slash_command)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let color_bands = *ctx.data().color_bands.lock().unwrap();
+    match stats::stats_embed(
+        &api::KenkooooApi,
+        user.trim(),
+        ctx.data().timezone(),
+        color_bands,
+    )
+    .await
+    {
+        Ok(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        Err(e) => match e.downcast::<rating::UserNotFound>() {
+            Ok(rating::UserNotFound(user)) => {
+                ctx.reply(format!("ユーザー ({}) は見つかりませんでした。", user))
+                    .await?;
+            }
+            Err(e) => return Err(e),
+        },
+    }
+    Ok(())
+}
+
+/// AtCoderのユーザーのレーティングと、直近のコンテスト成績を表示します。
+#[poise::command(slash_command)]
+pub async fn rating(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    match rating::rating_embed(user.trim()).await {
+        Ok(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        Err(e) => match e.downcast::<rating::UserNotFound>() {
+            Ok(rating::UserNotFound(user)) => {
+                ctx.reply(format!("ユーザー ({}) は見つかりませんでした。", user))
+                    .await?;
+            }
+            Err(e) => return Err(e),
+        },
+    }
+    Ok(())
+}
+
+/// AtCoderのユーザーのレーティングと、次の色までの進捗を表示します。
+#[poise::command(slash_command)]
+pub async fn progress(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+    #[description = "キャッシュを使わずにレーティングを取得し直す"] refresh: Option<bool>,
+) -> Result<(), Error> {
+    if refresh.unwrap_or(false) {
+        rating::invalidate_rating(user.trim());
+    }
+    ctx.defer().await?;
+    match rating::progress_embed(user.trim()).await {
+        Ok(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        Err(e) => match e.downcast::<rating::UserNotFound>() {
+            Ok(rating::UserNotFound(user)) => {
+                ctx.reply(format!("ユーザー ({}) は見つかりませんでした。", user))
+                    .await?;
+            }
+            Err(e) => return Err(e),
+        },
+    }
+    Ok(())
+}
+
+/// AtCoderのユーザーがACに使った言語を集計します。
+#[poise::command(slash_command, rename = "language-stats")]
+pub async fn languagestats(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+    #[description = "集計する日数 (省略時は全期間)"]
+    #[min = 1]
+    days: Option<u32>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    match languages::language_stats_embed(
+        &api::KenkooooApi,
+        user.trim(),
+        days,
+        ctx.data().timezone(),
+    )
+    .await?
+    {
+        Some(embed) => {
+            ctx.send(CreateReply::default().embed(embed)).await?;
+        }
+        None => {
+            ctx.reply(format!(
+                "ユーザー ({}) のACが見つかりませんでした。",
+                user.trim()
+            ))
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// AtCoderのユーザーが提出したもののACしていない問題を表示します。
+#[poise::command(slash_command)]
+pub async fn pending(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let user = user.trim();
+    match notify::pending_embeds(&api::KenkooooApi, ctx.data(), user).await? {
+        Some(pages) => paginate(ctx, &pages).await?,
+        None => {
+            ctx.reply(format!(
+                "ユーザー ({}) の未ACの問題は見つかりませんでした。",
+                user
+            ))
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// 最近のACから実力を推定し、少し難しい未ACの問題をおすすめします。
+#[poise::command(slash_command)]
+pub async fn recommend(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let embed = notify::recommend_embed(&api::KenkooooApi, ctx.data(), user.trim()).await?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// 問題と難易度のキャッシュを消して、すぐに取得し直します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn refresh(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer().await?;
+    let problem_data = api::refresh_problem_data(&api::KenkooooApi).await?;
+    ctx.reply(format!(
+        "問題データを再取得しました。(問題: {} 件 / 難易度: {} 件 / コンテスト: {} 件)",
+        problem_data.problems.len(),
+        problem_data.problem_models.len(),
+        problem_data.contests.len()
+    ))
+    .await?;
+    println!("Problem data refreshed");
+    Ok(())
+}
+
+/// AtCoderのユーザーの直近30日間の活動を表示します。
+#[poise::command(slash_command)]
+pub async fn activity(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let embed =
+        activity::activity_embed(&api::KenkooooApi, user.trim(), ctx.data().timezone()).await?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Botのバージョンなどの情報を表示します。
+#[poise::command(slash_command)]
+pub async fn about(ctx: Context<'_>) -> Result<(), Error> {
+    let users = ctx.data().users.lock().unwrap().len();
+    let embed = CreateEmbed::default()
+        .title(format!("AtCoder Bot v{}", env!("CARGO_PKG_VERSION")))
+        .description("毎日、登録されたユーザーが前日にACした問題を難易度付きで通知します。")
+        .field("サーバー数", ctx.cache().guild_count().to_string(), true)
+        .field("登録ユーザー数", users.to_string(), true);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// コマンドの一覧や、コマンドの使い方を表示します。
+#[poise::command(slash_command)]
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "詳しく知りたいコマンド (config channel など)"] command: Option<String>,
+) -> Result<(), Error> {
+    let commands = &ctx.framework().options().commands;
+    let Some(query) = command else {
+        paginate(ctx, &help::overview_embeds(commands)).await?;
+        return Ok(());
+    };
+    match help::command_embed(commands, &query) {
+        Some(embed) => {
+            ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+                .await?;
+        }
+        None => {
+            ctx.reply(format!(
+                "コマンド ({}) が見つかりませんでした。/help で一覧を表示できます。",
+                query.trim()
+            ))
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// 設定の一覧で、名前を表示するユーザーの最大数
+const CONFIG_USERS_SHOWN: usize = 50;
+
+/// 現在の設定を表示します。
+#[poise::command(slash_command, rename = "show")]
+pub async fn config_show(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let on_off = |enabled: bool| if enabled { "オン" } else { "オフ" };
+    let users = data.users.lock().unwrap().clone();
+    let mut user_list = users
+        .iter()
+        .take(CONFIG_USERS_SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if users.len() > CONFIG_USERS_SHOWN {
+        user_list.push_str(&format!(" ほか {} 人", users.len() - CONFIG_USERS_SHOWN));
+    }
+    if users.is_empty() {
+        user_list = messages::no_users().into();
+    }
+
+    let schedule = format!(
+        "{} ({}){}{}",
+        daily_job::report_time(data).format("%H:%M"),
+        data.timezone(),
+        match data.interval() {
+            1 => String::new(),
+            days => format!(" / {}日ごと", days),
+        },
+        if *data.paused.lock().unwrap() {
+            " / 一時停止中"
+        } else {
+            ""
+        }
+    );
+    let toggles = [
+        ("初ACのみ", *data.first_ac_only.lock().unwrap()),
+        ("挑戦 (未AC)", *data.show_attempts.lock().unwrap()),
+        ("コンテストごと", *data.group_by_contest.lock().unwrap()),
+        ("難易度の絵文字", *data.difficulty_emoji.lock().unwrap()),
+        ("難易度の信頼度", *data.mark_low_confidence.lock().unwrap()),
+        ("呼びかけ", *data.nudge_idle.lock().unwrap()),
+        (
+            "コンテストのリマインド",
+            *data.reminder_enabled.lock().unwrap(),
+        ),
+    ]
+    .iter()
+    .map(|(name, enabled)| format!("{}: {}", name, on_off(*enabled)))
+    .collect::<Vec<_>>()
+    .join("\n");
+    let format = [
+        format!(
+            "形式: {}",
+            match *data.post_format.lock().unwrap() {
+                notify::PostFormat::Embed => "埋め込み",
+                notify::PostFormat::Text => "テキスト",
+            }
+        ),
+        format!(
+            "色: {}",
+            match *data.embed_color.lock().unwrap() {
+                difficulty::EmbedColor::Difficulty => "難易度の色".into(),
+                difficulty::EmbedColor::Fixed(color) => format!("#{:06x}", color),
+            }
+        ),
+        format!("色の境界: {}", *data.color_bands.lock().unwrap()),
+        format!(
+            "言語: {}",
+            data.language_filter
+                .lock()
+                .unwrap()
+                .clone()
+                .unwrap_or("すべて".into())
+        ),
+    ]
+    .join("\n");
+    // ウェブフックのURLは秘密にすべきなので、設定されているかどうかだけを表示します
+    let destinations = [
+        format!(
+            "チャンネル: {}",
+            data.channel
+                .lock()
+                .unwrap()
+                .map_or("未設定".into(), |c| c.mention().to_string())
+        ),
+        format!(
+            "ロール: {}",
+            data.role
+                .lock()
+                .unwrap()
+                .map_or("なし".into(), |r| r.mention().to_string())
+        ),
+        format!(
+            "ウェブフック: {}",
+            if data.webhook.lock().unwrap().is_some() {
+                "設定済み"
+            } else {
+                "なし"
+            }
+        ),
+        format!("DMで受け取る人数: {}", data.dm.lock().unwrap().len()),
+    ]
+    .join("\n");
+
+    let embed = CreateEmbed::default()
+        .title("現在の設定")
+        .field("通知先", destinations, false)
+        .field("定期通知", schedule, false)
+        .field("表示", format, true)
+        .field("オプション", toggles, true)
+        .field(
+            messages::registered_list_title(users.len()),
+            user_list,
+            false,
+        );
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// 通知の設定や、最後の実行結果などBotの状態を表示します。
+#[poise::command(slash_command)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let channel = *data.channel.lock().unwrap();
+    let users = data.users.lock().unwrap().len();
+    let paused = *data.paused.lock().unwrap();
+    let tz = data.timezone();
+    let time = |t: DateTime<Utc>| t.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+
+    let next_run = match daily_job::next_run() {
+        Some(_) if paused => "一時停止中".into(),
+        Some(next) => time(next),
+        None => "未定".into(),
+    };
+    let last_run = match daily_job::last_run() {
+        Some((at, Ok(()))) => format!("{} (成功)", time(at)),
+        Some((at, Err(e))) => format!("{} (失敗: {})", time(at), e),
+        None => "起動後まだ実行していません".into(),
+    };
+    let problem_data = match api::problem_data_fetched_at() {
+        Some(fetched_at) => format!(
+            "{}分前に取得",
+            (chrono::Local::now() - fetched_at).num_minutes()
+        ),
+        None => "未取得".into(),
+    };
+    let embed = CreateEmbed::default()
+        .title("Botの状態")
+        .field(
+            "通知先のチャンネル",
+            channel.map_or("未設定".into(), |c| c.mention().to_string()),
+            true,
+        )
+        .field("登録ユーザー数", users.to_string(), true)
+        .field("次の定期通知", next_run, false)
+        .field("最後の通知", last_run, false)
+        .field("問題データ", problem_data, false);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// 今月の目標AC数を設定・表示します。
+#[poise::command(slash_command, subcommands("goal_set", "goal_show"))]
+pub async fn goal(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// 今月の目標AC数を設定します。0を指定すると目標を解除します。
+#[poise::command(slash_command, rename = "set")]
+pub async fn goal_set(
+    ctx: Context<'_>,
+    #[description = "目標AC数"] count: u32,
+    #[description = "AtCoderのユーザー名 (省略時は自分)"] user: Option<String>,
+) -> Result<(), Error> {
+    let Some(user) = linked_or_given_user(ctx, user) else {
+        ctx.reply("ユーザーが紐付けられていません。ユーザー名を指定してください。")
+            .await?;
+        return Ok(());
+    };
+    let Some(user) = find_user(ctx.data(), &user).pop() else {
+        ctx.reply(messages::user_not_registered(&user)).await?;
+        return Ok(());
+    };
+    // 管理者か、そのユーザーに紐付けられたDiscordアカウントだけが変更できます
+    let linked = ctx.data().links.lock().unwrap().get(&user).copied();
+    if !is_admin(ctx).await && linked != Some(ctx.author().id) {
+        ctx.reply(format!(
+            "ユーザー ({}) の目標は、管理者か /config user link で紐付けた本人だけが設定できます。",
+            user
+        ))
+        .await?;
+        return Ok(());
+    }
+    {
+        if count == 0 {
+            ctx.data().goals.lock().unwrap().remove(&user);
+        } else {
+            ctx.data().goals.lock().unwrap().insert(user.clone(), count);
+        }
+        save(ctx.data())?;
+    }
+    if count == 0 {
+        ctx.reply(format!("ユーザー ({}) の目標を解除しました。", user))
+            .await?;
+    } else {
+        ctx.reply(format!(
+            "ユーザー ({}) の今月の目標を {} 問に設定しました。",
+            user, count
+        ))
+        .await?;
+    }
+    println!("Goal set: {:?} {:?}", &user, count);
+    Ok(())
+}
+
+/// 今月の目標AC数に対する進捗を表示します。
+#[poise::command(slash_command, rename = "show")]
+pub async fn goal_show(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名 (省略時は自分)"] user: Option<String>,
+) -> Result<(), Error> {
+    let Some(user) = linked_or_given_user(ctx, user) else {
+        ctx.reply("ユーザーが紐付けられていません。ユーザー名を指定してください。")
+            .await?;
+        return Ok(());
+    };
+    let Some(goal) = ctx.data().goals.lock().unwrap().get(&user).copied() else {
+        ctx.reply(format!("ユーザー ({}) の目標は設定されていません。", user))
+            .await?;
+        return Ok(());
+    };
+    ctx.defer().await?;
+    let solved =
+        notify::count_monthly_solved(&api::KenkooooApi, &user, ctx.data().timezone()).await?;
+    ctx.reply(format!(
+        "ユーザー ({}) の今月の進捗: {}",
+        user,
+        notify::goal_progress(solved, goal)
+    ))
+    .await?;
+    Ok(())
+}
+
+/// ユーザー名が指定されていればそのユーザーを、なければ実行者に紐付いたユーザーを返します。
+fn linked_or_given_user(ctx: Context<'_>, user: Option<String>) -> Option<String> {
+    match user {
+        Some(user) => Some(find_user(ctx.data(), &user).pop().unwrap_or(user)),
+        None => ctx
+            .data()
+            .links
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, &id)| id == ctx.author().id)
+            .map(|(user, _)| user.clone()),
+    }
+}
+
+/// タイムゾーン名が正しくないときに示す例
+const TIMEZONE_EXAMPLES: [&str; 5] = [
+    "Asia/Tokyo",
+    "UTC",
+    "America/New_York",
+    "Europe/London",
+    "Asia/Shanghai",
+];
+
+/// 日付の区切りに使うタイムゾーンを設定します。
+#[poise::command(slash_command)]
+pub async fn timezone(
+    ctx: Context<'_>,
+    #[description = "IANAのタイムゾーン名 (Asia/Tokyo など)"] timezone: String,
+) -> Result<(), Error> {
+    let Ok(tz) = timezone.trim().parse::<Tz>() else {
+        ctx.reply(format!(
+            "タイムゾーン ({}) が見つかりません。\n例: {}",
+            timezone,
+            TIMEZONE_EXAMPLES.join(", ")
+        ))
+        .await?;
+        return Ok(());
+    };
+    {
+        ctx.data().timezone.lock().unwrap().replace(tz);
+        save(ctx.data())?;
+    }
+    // 日付の区切りが変わるため、次の定期通知の時刻も求め直します
+    daily_job::reschedule();
+    ctx.reply(format!("タイムゾーンを {} に設定しました。", tz))
+        .await?;
+    println!("Timezone set: {:?}", tz);
+    Ok(())
+}
+
+/// 定期通知の間隔を設定します。その日数分のACをまとめて通知します。
+#[poise::command(slash_command)]
+pub async fn interval(
+    ctx: Context<'_>,
+    #[description = "通知の間隔 (日数)"] days: u32,
+) -> Result<(), Error> {
+    if !(1..=notify::MAX_REPORT_DAYS).contains(&days) {
+        ctx.reply(format!(
+            "間隔は1〜{}日で指定してください。",
+            notify::MAX_REPORT_DAYS
+        ))
+        .await?;
+        return Ok(());
+    }
+    {
+        ctx.data().interval.lock().unwrap().replace(days);
+        save(ctx.data())?;
+    }
+    ctx.reply(if days == 1 {
+        "毎日通知するように設定しました。".into()
+    } else {
+        format!(
+            "{}日ごとに、{}日分のACを通知するように設定しました。",
+            days, days
+        )
+    })
+    .await?;
+    println!("Interval set: {:?}", days);
+    Ok(())
+}
+
+/// 定期通知を行う時刻を設定します。
+#[poise::command(slash_command)]
+pub async fn schedule(
+    ctx: Context<'_>,
+    #[description = "時 (0〜23)"]
+    #[max = 23]
+    hour: u32,
+    #[description = "分 (0〜59、省略時は0)"]
+    #[max = 59]
+    minute: Option<u32>,
+) -> Result<(), Error> {
+    let Some(time) = NaiveTime::from_hms_opt(hour, minute.unwrap_or(0), 0) else {
+        ctx.reply("時刻は0:00〜23:59で指定してください。").await?;
+        return Ok(());
+    };
+    {
+        ctx.data().report_time.lock().unwrap().replace(time);
+        save(ctx.data())?;
+    }
+    daily_job::reschedule();
+    let timezone = ctx.data().timezone();
+    let mut message = vec![format!(
+        "毎日 {} ({}) に通知するように設定しました。",
+        time.format("%H:%M"),
+        timezone
+    )];
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    message.extend(
+        daily_job::time_conflicts(timezone, time, today)
+            .iter()
+            .map(messages::time_conflict),
+    );
+    ctx.reply(message.join("\n")).await?;
+    println!("Report time set: {:?}", time);
+    Ok(())
+}
+
+/// コンテストの開始前にリマインドするかと、その何分前かを設定します。
+#[poise::command(slash_command)]
+pub async fn reminder(
+    ctx: Context<'_>,
+    #[description = "リマインドする場合はTrue"] enabled: bool,
+    #[description = "開始の何分前か (省略時は30分)"]
+    #[min = 1]
+    #[max = 1440]
+    minutes: Option<u32>,
+) -> Result<(), Error> {
+    {
+        *ctx.data().reminder_enabled.lock().unwrap() = enabled;
+        if minutes.is_some() {
+            *ctx.data().reminder_minutes.lock().unwrap() = minutes;
+        }
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        format!(
+            "コンテストの{}分前にリマインドするように設定しました。",
+            contest_reminder::reminder_minutes(ctx.data())
+        )
+    } else {
+        "コンテストのリマインドを無効にしました。".into()
+    })
+    .await?;
+    println!("Contest reminder set: {:?} {:?}", enabled, minutes);
+    Ok(())
+}
+
+/// 問題の前に難易度の色の絵文字 (🟩 など) を付けるか設定します。
+#[poise::command(slash_command)]
+pub async fn emoji(
+    ctx: Context<'_>,
+    #[description = "絵文字を付ける場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    {
+        *ctx.data().difficulty_emoji.lock().unwrap() = enabled;
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        "問題の前に難易度の色の絵文字を付けるように設定しました。"
+    } else {
+        "難易度の色の絵文字を付けないように設定しました。"
+    })
+    .await?;
+    println!("Difficulty emoji set: {:?}", enabled);
+    Ok(())
+}
+
+/// 難易度の色と、それぞれの色になる難易度の範囲を表示します。
+#[poise::command(slash_command)]
+pub async fn colors(
+    ctx: Context<'_>,
+    #[description = "全員に見えるように投稿する場合はTrue"] public: Option<bool>,
+) -> Result<(), Error> {
+    let bands = *ctx.data().color_bands.lock().unwrap();
+    let lines = bands
+        .ranges()
+        .into_iter()
+        .map(|(color, lower, upper)| {
+            format!(
+                "{} {} ({}) {}〜{} `#{:06x}`",
+                color.emoji(),
+                color,
+                color.english_name(),
+                lower,
+                upper.map(|u| u.to_string()).unwrap_or_default(),
+                u32::from(color)
+            )
+        })
+        .collect::<Vec<_>>();
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title("難易度の色")
+                    .description(lines.join("\n")),
+            )
+            .ephemeral(!public.unwrap_or(false)),
+    )
+    .await?;
+    Ok(())
+}
+
+/// 信頼度の低い難易度の推定値に「±」を付けるかどうかを設定します。
+#[poise::command(slash_command)]
+pub async fn confidence(
+    ctx: Context<'_>,
+    #[description = "印を付ける場合はTrue"] enabled: bool,
+) -> Result<(), Error> {
+    {
+        *ctx.data().mark_low_confidence.lock().unwrap() = enabled;
+        save(ctx.data())?;
+    }
+    ctx.reply(if enabled {
+        "信頼度の低い難易度に「±」を付けるように設定しました。"
+    } else {
+        "難易度の信頼度の印を付けないように設定しました。"
+    })
+    .await?;
+    println!("Mark low confidence set: {:?}", enabled);
+    Ok(())
+}
+
+/// 難易度の色の境界を設定します。省略するとAtCoderの標準に戻します。
+#[poise::command(slash_command)]
+pub async fn colorbands(
+    ctx: Context<'_>,
+    #[description = "茶〜赤の下限をカンマ区切りで7つ (例: 400,800,1200,1600,2000,2400,2800)"]
+    bounds: Option<String>,
+) -> Result<(), Error> {
+    let bands = match bounds {
+        Some(bounds) => {
+            let bands = bounds
+                .split(',')
+                .map(|b| b.trim().parse::<u32>())
+                .collect::<Result<Vec<_>, _>>()
+                .ok()
+                .and_then(|bounds| difficulty::ColorBands::new(&bounds));
+            let Some(bands) = bands else {
+                ctx.reply(
+                    "境界は、小さい順に並んだ7つの正の整数をカンマ区切りで指定してください。",
+                )
+                .await?;
+                return Ok(());
+            };
+            bands
+        }
+        None => difficulty::ColorBands::default(),
+    };
+    {
+        *ctx.data().color_bands.lock().unwrap() = bands;
+        save(ctx.data())?;
+    }
+    ctx.reply(format!("難易度の色の境界を {} に設定しました。", bands))
+        .await?;
+    println!("Color bands set: {:?}", bands);
+    Ok(())
+}
+
+/// 通知を転送するウェブフック (Slackなど) を設定します。省略で解除
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn webhook(
+    ctx: Context<'_>,
+    #[description = "ウェブフックのURL (https://...)"] url: Option<String>,
+) -> Result<(), Error> {
+    let url = match url {
+        Some(url) => match reqwest::Url::parse(url.trim()) {
+            Ok(url) if url.scheme() == "https" && url.host().is_some() => Some(url.to_string()),
+            _ => {
+                ctx.reply("URLが正しくありません。https:// から始まるURLを指定してください。")
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    {
+        *ctx.data().webhook.lock().unwrap() = url.clone();
+        save(ctx.data())?;
+    }
+    ctx.reply(if url.is_some() {
+        "ウェブフックへの転送を設定しました。"
+    } else {
+        "ウェブフックへの転送を解除しました。"
+    })
+    .await?;
+    println!("Webhook set: {:?}", url.is_some());
+    Ok(())
+}
+
+/// 定期通知を一時停止します。停止中も設定は残り、/run は使えます。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn pause(ctx: Context<'_>) -> Result<(), Error> {
+    set_paused(ctx, true).await
+}
+
+/// 一時停止していた定期通知を再開します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn resume(ctx: Context<'_>) -> Result<(), Error> {
+    set_paused(ctx, false).await
+}
+
+async fn set_paused(ctx: Context<'_>, paused: bool) -> Result<(), Error> {
+    let (was_paused, since) = {
+        let was_paused = std::mem::replace(&mut *ctx.data().paused.lock().unwrap(), paused);
+        let mut paused_since = ctx.data().paused_since.lock().unwrap();
+        let since = *paused_since;
+        if paused && !was_paused {
+            *paused_since = Some(Utc::now());
+        } else if !paused {
+            *paused_since = None;
+        }
+        drop(paused_since);
+        save(ctx.data())?;
+        (was_paused, since)
+    };
+    ctx.reply(match (paused, was_paused) {
+        (true, false) => "定期通知を一時停止しました。/config resume で再開できます。".into(),
+        (true, true) => format!("定期通知は既に{}一時停止しています。", paused_for(since)),
+        (false, true) if since.is_some() => format!(
+            "定期通知を再開しました。({}停止していました)",
+            paused_for(since)
+        ),
+        (false, true) => "定期通知を再開しました。".into(),
+        (false, false) => "定期通知は一時停止していません。".into(),
+    })
+    .await?;
+    println!("Paused set: {:?}", paused);
+    Ok(())
+}
+
+/// 一時停止していた期間 (`3日と2時間` など)。停止した時刻が分からない場合は空です。
+fn paused_for(since: Option<DateTime<Utc>>) -> String {
+    let Some(since) = since else {
+        return String::new();
+    };
+    let elapsed = Utc::now() - since;
+    if elapsed.num_days() > 0 {
+        format!("{}日と{}時間", elapsed.num_days(), elapsed.num_hours() % 24)
+    } else {
+        format!("{}時間", elapsed.num_hours())
+    }
+}
+
+/// 設定されたチャンネルにテスト用の投稿をします。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn testpost(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(channel) = *ctx.data().channel.lock().unwrap() else {
+        ctx.reply("チャンネルが設定されていません。/channel で設定してください。")
+            .await?;
+        return Ok(());
+    };
+    let message = notify::new_message(None).embed(notify::sample_embed());
+    match channel.send_message(ctx, message).await {
+        Ok(_) => {
+            ctx.reply(format!("{} にテスト投稿しました。", channel.mention()))
+                .await?
+        }
+        Err(e) => {
+            ctx.reply(format!(
+                "{} への投稿に失敗しました: {}",
+                channel.mention(),
+                e
+            ))
+            .await?
+        }
+    };
+    Ok(())
+}
+
+/// 最後のACから指定した日数以上経ったユーザーを表示します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn inactive(
+    ctx: Context<'_>,
+    #[description = "ACしていない日数"]
+    #[min = 1]
+    days: u32,
+) -> Result<(), Error> {
+    let users = ctx.data().users.lock().unwrap().clone();
+    ctx.defer().await?;
+    let inactive = notify::inactive_users(&api::KenkooooApi, &users, days).await;
+    if inactive.is_empty() {
+        ctx.reply(format!("{}日以上ACしていないユーザーはいません。", days))
+            .await?;
+        return Ok(());
+    }
+    let lines = inactive_lines(&inactive);
+    let title = format!("{}日以上ACしていないユーザー ({}人)", days, lines.len());
+    let pages = lines
+        .chunks(20)
+        .map(|lines| {
+            CreateEmbed::default()
+                .title(&title)
+                .description(lines.join("\n"))
+        })
+        .collect::<Vec<_>>();
+    paginate(ctx, &pages).await?;
+    Ok(())
+}
+
+/// `ユーザー名 — 3日前` の形で、ユーザーごとに最後のACからの日数を並べます。
+fn inactive_lines(inactive: &[(String, Option<DateTime<Utc>>)]) -> Vec<String> {
+    let now = Utc::now();
+    inactive
+        .iter()
+        .map(|(user, last)| match last {
+            Some(last) => format!("{} — {}日前", user, (now - last).num_days()),
+            None => format!("{} — ACなし", user),
+        })
+        .collect()
+}
+
+/// 最後のACから指定した日数以上経ったユーザーを登録解除します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn removeinactive(
+    ctx: Context<'_>,
+    #[description = "ACしていない日数"]
+    #[min = 1]
+    days: u32,
+) -> Result<(), Error> {
+    let users = ctx.data().users.lock().unwrap().clone();
+    ctx.defer().await?;
+    let inactive = notify::inactive_users(&api::KenkooooApi, &users, days).await;
+    if inactive.is_empty() {
+        ctx.reply(format!("{}日以上ACしていないユーザーはいません。", days))
+            .await?;
+        return Ok(());
+    }
+
+    let mut lines = vec![format!(
+        "{}人中{}人を登録解除します。よろしいですか？",
+        users.len(),
+        inactive.len()
+    )];
+    if inactive.len() == users.len() {
+        lines.push("**登録されているユーザー全員が対象です。**".into());
+    }
+    lines.extend(inactive_lines(&inactive));
+    let content = notify::chunk_lines(&lines).swap_remove(0);
+
+    let (confirmed, reply) = confirm(ctx, &content, "登録解除する").await?;
+    if !confirmed {
+        reply
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .content("登録解除を取りやめました。")
+                    .components(vec![]),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let removed = inactive
+        .into_iter()
+        .map(|(user, _)| user)
+        .collect::<Vec<_>>();
+    {
+        for user in &removed {
+            ctx.data().users.lock().unwrap().remove(user);
+            ctx.data().links.lock().unwrap().remove(user);
+            ctx.data().display_names.lock().unwrap().remove(user);
+        }
+        save(ctx.data())?;
+    }
+    let lines = [
+        vec![format!("{}人を登録解除しました。", removed.len())],
+        removed.clone(),
+    ]
+    .concat();
+    let content = notify::chunk_lines(&lines).swap_remove(0);
+    reply
+        .edit(
+            ctx,
+            CreateReply::default().content(content).components(vec![]),
+        )
+        .await?;
+    println!("Inactive users removed: {:?}", &removed);
+    Ok(())
+}
+
+/// 指定した問題をACした登録ユーザーを表示します。
+#[poise::command(slash_command)]
+pub async fn problemsolvers(
+    ctx: Context<'_>,
+    #[description = "問題ID (abc300_a など) または問題のURL"] problem: String,
+) -> Result<(), Error> {
+    let problem_id = parse_problem_id(&problem);
+    ctx.defer().await?;
+    let problem_data = api::problem_data(&api::KenkooooApi).await?;
+    let Some(problem) = problem_data.problems.get(&problem_id) else {
+        ctx.reply(format!("問題 ({}) が見つかりませんでした。", problem_id))
+            .await?;
+        return Ok(());
+    };
+    let users = ctx.data().users.lock().unwrap().clone();
+    let solved = join_all(
+        users
+            .iter()
+            .map(|user| notify::has_solved(&api::KenkooooApi, user, &problem_id)),
+    )
+    .await;
+    let mut solvers = vec![];
+    let mut others = vec![];
+    for (user, solved) in users.iter().zip(solved) {
+        match solved {
+            Ok(true) => solvers.push(user.clone()),
+            Ok(false) => others.push(user.clone()),
+            Err(e) => println!("Skipping user {}: {:?}", user, e),
+        }
+    }
+    let list = |users: &[String]| {
+        if users.is_empty() {
+            "なし".to_string()
+        } else {
+            users.join("\n")
+        }
+    };
+    let embed = CreateEmbed::default()
+        .title(format!("{} をACしたユーザー", problem.title))
+        .url(format!(
+            "https://atcoder.jp/contests/{}/tasks/{}",
+            problem.contest_id, problem.id
+        ))
+        .field(format!("AC ({}人)", solvers.len()), list(&solvers), true)
+        .field(format!("未AC ({}人)", others.len()), list(&others), true);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// 登録ユーザーがコンテストの問題を解いた数を、多い順に表示します。
+#[poise::command(slash_command)]
+pub async fn mostsolved(
+    ctx: Context<'_>,
+    #[description = "コンテストID (abc300 など) またはコンテストのURL"] contest: String,
+) -> Result<(), Error> {
+    let contest_id = parse_contest_id(&contest);
+    ctx.defer().await?;
+    let problem_data = api::problem_data(&api::KenkooooApi).await?;
+    let problems = problem_data
+        .problems
+        .values()
+        .filter(|p| p.contest_id == contest_id)
+        .map(|p| p.id.as_str())
+        .collect::<BTreeSet<_>>();
+    if problems.is_empty() {
+        ctx.reply(format!(
+            "コンテスト ({}) が見つかりませんでした。",
+            contest_id
+        ))
+        .await?;
+        return Ok(());
+    }
+    let contest_title = problem_data
+        .contests
+        .get(&contest_id)
+        .map_or(contest_id.clone(), |c| c.title.clone());
+
+    let users = ctx.data().users.lock().unwrap().clone();
+    let solved = join_all(
+        users
+            .iter()
+            .map(|user| notify::solved_problems(&api::KenkooooApi, user)),
+    )
+    .await;
+    let mut ranking = vec![];
+    for (user, solved) in users.iter().zip(solved) {
+        match solved {
+            Ok(solved) => {
+                let count = problems.iter().filter(|&&p| solved.contains(p)).count();
+                ranking.push((user, count));
+            }
+            Err(e) => println!("Skipping user {}: {:?}", user, e),
+        }
+    }
+    ranking.sort_by(|(a_user, a), (b_user, b)| b.cmp(a).then(a_user.cmp(b_user)));
+
+    let lines = ranking
+        .iter()
+        .map(|&(user, count)| {
+            let result = if count == problems.len() {
+                "完答".to_string()
+            } else {
+                format!("{} / {} 問", count, problems.len())
+            };
+            let rank = ranking.iter().filter(|&&(_, c)| c > count).count() + 1;
+            format!("{}. {} — {}", rank, user, result)
+        })
+        .collect::<Vec<_>>();
+    let embed = CreateEmbed::default()
+        .title(format!("{} の解いた問題数", contest_title))
+        .url(format!("https://atcoder.jp/contests/{}", contest_id))
+        .description(if lines.is_empty() {
+            "ユーザーが登録されていません。".into()
+        } else {
+            lines.join("\n")
+        });
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// これから開催されるAtCoderのコンテストを表示します。
+#[poise::command(slash_command)]
+pub async fn contests(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer().await?;
+    let embed = contests::upcoming_embed(&api::KenkooooApi, ctx.data().timezone()).await?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// 登録ユーザーを、期間内にACした問題の数の多い順に表示します。
+#[poise::command(slash_command)]
+pub async fn ranking(
+    ctx: Context<'_>,
+    #[description = "集計期間 (省略時は昨日)"] period: Option<notify::RankingPeriod>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let period = period.unwrap_or(notify::RankingPeriod::Yesterday);
+    let embed = notify::ranking_embed(&api::KenkooooApi, ctx.data(), period).await?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// 登録ユーザーを、連続でACしている日数の長い順に表示します。
+#[poise::command(slash_command)]
+pub async fn streakleaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer().await?;
+    let users = ctx.data().users.lock().unwrap().clone();
+    let embed =
+        streak::streak_leaderboard_embed(&api::KenkooooApi, &users, ctx.data().timezone()).await?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// コンテストIDかコンテストのURLから、コンテストIDを取り出します。
+fn parse_contest_id(contest: &str) -> String {
+    let contest = contest.trim();
+    match contest.split_once("/contests/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => contest,
+    }
+    .to_string()
+}
+
+/// 提出のURL (`https://atcoder.jp/contests/abc300/submissions/12345`) から、コンテストIDと提出IDを取り出します。
+/// 提出のURLでない場合は `None` を返します。
+fn parse_submission_url(url: &str) -> Option<(String, String)> {
+    let (_, rest) = url.trim().split_once("/contests/")?;
+    let mut segments = rest.split(['/', '?', '#']);
+    let contest_id = segments.next()?;
+    if segments.next()? != "submissions" {
+        return None;
+    }
+    let submission_id = segments.next()?;
+    if submission_id.is_empty() || !submission_id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((contest_id.to_string(), submission_id.to_string()))
+}
+
+/// 問題IDか問題のURLから、問題IDを取り出します。
+fn parse_problem_id(problem: &str) -> String {
+    problem
+        .trim()
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// カンマか空白で区切られたユーザー名を分割します。
+fn split_users(users: &str) -> Vec<String> {
+    users
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|u| !u.is_empty())
+        .map(|u| u.to_string())
+        .collect()
+}
+
+/// デバッグ用の出力の最大文字数 (コードブロックの記号の分を除く)
+const DEBUG_OUTPUT_LIMIT: usize = 1900;
+
+/// 調査用に、ユーザーの提出と問題データの対応を表示します。
+#[poise::command(
+    slash_command,
+    default_member_permissions = "ADMINISTRATOR",
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn debuguser(
+    ctx: Context<'_>,
+    #[description = "AtCoderのユーザー名"] user: String,
+    #[description = "遡る日数 (省略時は1日)"]
+    #[min = 1]
+    days: Option<u32>,
+) -> Result<(), Error> {
+    let user = user.trim();
+    let days = days.unwrap_or(1).clamp(1, notify::MAX_REPORT_DAYS);
+    ctx.defer_ephemeral().await?;
+    let from = notify::start_of_day(ctx.data().timezone(), days.into()).timestamp();
+    let problem_data = api::problem_data(&api::KenkooooApi).await?;
+    let submissions = api::fetch_submission_history(&api::KenkooooApi, user, from).await?;
+
+    let registered = find_user(ctx.data(), user);
+    let canonical = submissions.first().map(|s| s.user_id.clone());
+    let mut lines = vec![
+        format!(
+            "登録名: {}",
+            if registered.is_empty() {
+                "未登録".into()
+            } else {
+                registered.join(", ")
+            }
+        ),
+        format!(
+            "提出上の表記: {}",
+            canonical.unwrap_or("不明 (提出なし)".into())
+        ),
+        format!(
+            "問題データ: 問題 {} 件 / 難易度 {} 件 (取得: {})",
+            problem_data.problems.len(),
+            problem_data.problem_models.len(),
+            problem_data.fetched_at.format("%m/%d %H:%M")
+        ),
+        format!(
+            "過去{}日間の提出: {} 件 (AC: {} 件)",
+            days,
+            submissions.len(),
+            submissions
+                .iter()
+                .filter(|s| s.result == api::JudgeStatus::Ac)
+                .count()
+        ),
+    ];
+    for s in submissions
+        .iter()
+        .filter(|s| s.result == api::JudgeStatus::Ac)
+    {
+        let found = if problem_data.problems.contains_key(&s.problem_id) {
+            "問題あり"
+        } else {
+            "問題なし"
+        };
+        let difficulty = match problem_data.difficulty(&s.problem_id) {
+            Some((d, false)) => d.to_string(),
+            Some((d, true)) => format!("約{}", d),
+            None => "難易度なし".into(),
+        };
+        lines.push(format!(
+            "{} ({}) {} / {}",
+            s.problem_id, s.contest_id, found, difficulty
+        ));
+    }
+
+    let mut output = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if output.chars().count() + line.chars().count() + 1 > DEBUG_OUTPUT_LIMIT {
+            output.push_str(&format!("…ほか {} 行", lines.len() - i));
+            break;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    ctx.send(
+        CreateReply::default()
+            .content(format!("```\n{}\n```", output.trim_end()))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// `content` と確認のボタンを表示し、実行者が確認のボタンを押したかどうかを返します。
+/// キャンセルされた場合や、[`CONFIRM_TIMEOUT`] の間押されなかった場合は `false` を返します。
+async fn confirm<'a>(
+    ctx: Context<'a>,
+    content: &str,
+    confirm_label: &str,
+) -> Result<(bool, poise::ReplyHandle<'a>), Error> {
+    let ctx_id = ctx.id();
+    let confirm_button_id = format!("{}confirm", ctx_id);
+    let cancel_button_id = format!("{}cancel", ctx_id);
+    let reply =
+        ctx.send(CreateReply::default().content(content).components(vec![
+            CreateActionRow::Buttons(vec![
+                    CreateButton::new(&confirm_button_id)
+                        .label(confirm_label)
+                        .style(serenity::ButtonStyle::Danger),
+                    CreateButton::new(&cancel_button_id).label("キャンセル"),
+                ]),
+        ]))
+        .await?;
+
+    let author = ctx.author().id;
+    let press = ComponentInteractionCollector::new(ctx)
+        .filter(move |press| {
+            press.data.custom_id.starts_with(&ctx_id.to_string()) && press.user.id == author
+        })
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+    let confirmed = press
+        .as_ref()
+        .is_some_and(|press| press.data.custom_id == confirm_button_id);
+    if let Some(press) = press {
+        press
+            .create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await?;
+    }
+    Ok((confirmed, reply))
+}
+
+/// 大文字小文字を区別せずに、登録されているユーザー名を探します。
+fn find_user(data: &Data, user: &str) -> Vec<String> {
+    data.users
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|u| u.eq_ignore_ascii_case(user))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_user_ignores_case() {
+        let data = Data::default();
+        data.users.lock().unwrap().insert("tourist".into());
+        assert_eq!(find_user(&data, "Tourist"), ["tourist"]);
+        assert_eq!(find_user(&data, "TOURIST"), ["tourist"]);
+        assert!(find_user(&data, "tourist2").is_empty());
+    }
+
+    #[test]
+    fn split_users_accepts_commas_and_whitespace() {
+        assert_eq!(
+            split_users(" alice, bob  carol,,dave\teve\n"),
+            ["alice", "bob", "carol", "dave", "eve"]
+        );
+        assert!(split_users(" , ").is_empty());
+    }
+
+    #[test]
+    fn mixed_case_duplicates_are_already_registered() {
+        let data = Data::default();
+        data.users.lock().unwrap().insert("Tourist".into());
+        let registered = split_users("tourist TOURIST")
+            .iter()
+            .filter_map(|user| find_user(&data, user).pop())
+            .collect::<Vec<_>>();
+        assert_eq!(registered, ["Tourist", "Tourist"]);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    api::{self, AtcoderApi, JudgeStatus},
+    difficulty, notify, rating,
+};
+use anyhow::Error;
+use chrono_tz::Tz;
+use itertools::Itertools;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// 比較に使うユーザー1人分の成績
+struct UserStats {
+    user: String,
+    /// 現在のレーティング。Ratedのコンテストに参加していない場合は `None`
+    rating: Option<i64>,
+    /// ACした問題の数
+    solved: usize,
+    /// 難易度の色ごとのACした問題の数
+    solved_by_color: BTreeMap<difficulty::Color, usize>,
+    /// 昨日ACした問題の数
+    solved_yesterday: usize,
+}
+
+impl UserStats {
+    fn summary(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "レーティング: {}",
+                self.rating
+                    .map(|r| r.to_string())
+                    .unwrap_or("未参加".into())
+            ),
+            format!("AC数: {} 問", self.solved),
+            format!("昨日のAC数: {} 問", self.solved_yesterday),
+        ];
+        lines.extend(
+            self.solved_by_color
+                .iter()
+                .rev()
+                .map(|(&color, count)| format!("{}: {} 問", color, count)),
+        );
+        lines.join("\n")
+    }
+}
+
+/// ユーザーの成績を集計します。ユーザーが存在しない場合は [`rating::UserNotFound`] を返します。
+async fn user_stats(
+    api: &dyn AtcoderApi,
+    user: &str,
+    tz: Tz,
+    color_bands: difficulty::ColorBands,
+) -> Result<UserStats, Error> {
+    let rating = rating::current_rating(user).await?;
+    let problem_data = api::problem_data(api).await?;
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+
+    let accepted = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .unique_by(|s| &s.problem_id)
+        .collect::<Vec<_>>();
+    let from = notify::start_of_day(tz, 1).timestamp();
+    let to = notify::start_of_day(tz, 0).timestamp();
+    let solved_yesterday = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .filter(|s| (from..to).contains(&s.epoch_second))
+        .map(|s| &s.problem_id)
+        .unique()
+        .count();
+
+    Ok(UserStats {
+        user: submissions
+            .first()
+            .map(|s| s.user_id.clone())
+            .unwrap_or(user.to_string()),
+        rating,
+        solved: accepted.len(),
+        solved_by_color: accepted
+            .iter()
+            .map(|s| {
+                problem_data
+                    .difficulty(&s.problem_id)
+                    .map(|(d, _)| color_bands.difficulty_color(d))
+                    .unwrap_or(difficulty::Color::Black)
+            })
+            .counts()
+            .into_iter()
+            .collect(),
+        solved_yesterday,
+    })
+}
+
+/// 2人のユーザーの成績を並べた埋め込みを作ります。
+pub async fn compare_embed(
+    api: &dyn AtcoderApi,
+    user_a: &str,
+    user_b: &str,
+    tz: Tz,
+    color_bands: difficulty::ColorBands,
+) -> Result<CreateEmbed, Error> {
+    let (a, b) = tokio::try_join!(
+        user_stats(api, user_a, tz, color_bands),
+        user_stats(api, user_b, tz, color_bands)
+    )?;
+    Ok(CreateEmbed::default()
+        .title(format!("{} vs {}", a.user, b.user))
+        .field(&a.user, a.summary(), true)
+        .field(&b.user, b.summary(), true))
+}
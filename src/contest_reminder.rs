@@ -0,0 +1,129 @@
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use crate::{api, config_path, contests, load, notify, Data};
+use anyhow::Error;
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+use tokio::time::sleep;
+
+/// コンテストの何分前にリマインドするかの既定値
+pub const DEFAULT_REMINDER_MINUTES: u32 = 30;
+
+/// 設定やコンテストの一覧の変化を確かめる間隔
+const REMINDER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// リマインドしたコンテストを記録するファイルの名前。再起動しても同じコンテストを二重に通知しないようにします
+const REMINDED_CONTESTS_FILE: &str = "reminded_contests.json";
+
+/// コンテストの何分前にリマインドするか
+pub fn reminder_minutes(data: &Data) -> u32 {
+    data.reminder_minutes
+        .lock()
+        .unwrap()
+        .unwrap_or(DEFAULT_REMINDER_MINUTES)
+}
+
+/// リマインドしたコンテストを記録するファイルのパス。設定ファイルと同じディレクトリに置きます
+fn reminded_contests_path() -> PathBuf {
+    PathBuf::from(config_path()).with_file_name(REMINDED_CONTESTS_FILE)
+}
+
+fn load_reminded() -> BTreeSet<String> {
+    fs::read_to_string(reminded_contests_path())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_reminded(reminded: &BTreeSet<String>) -> Result<(), Error> {
+    fs::write(reminded_contests_path(), serde_json::to_string(reminded)?)?;
+    Ok(())
+}
+
+/// コンテストのリマインドの埋め込みを作ります。
+fn reminder_embed(contest: &api::ContestItem, data: &Data) -> CreateEmbed {
+    let minutes = (contest.start_epoch_second - Utc::now().timestamp()).max(0) / 60;
+    CreateEmbed::default()
+        .title(format!(
+            "{} {} があと{}分で始まります",
+            contests::kind_emoji(contest),
+            contest.title,
+            minutes
+        ))
+        .url(contests::contest_url(contest))
+        .field("開始", contests::start_text(contest, data.timezone()), true)
+        .field("時間", contests::duration_text(contest), true)
+        .field("Rated", contests::rated_text(contest), true)
+}
+
+/// コンテストが始まる前に、設定されたチャンネルでリマインドし続けます。
+/// 設定やコンテストの一覧は [`REMINDER_POLL_INTERVAL`] ごとに確かめ直します。
+pub async fn wait(ctx: serenity::Context) {
+    loop {
+        let next = match remind_due(&ctx).await {
+            Ok(next) => next,
+            Err(e) => {
+                println!("Failed to check contest reminders: {:?}", e);
+                None
+            }
+        };
+        let wait = next
+            .map(|next| (next - Utc::now().timestamp()).max(0) as u64)
+            .map(std::time::Duration::from_secs)
+            .map_or(REMINDER_POLL_INTERVAL, |next| {
+                next.min(REMINDER_POLL_INTERVAL)
+            });
+        sleep(wait).await;
+    }
+}
+
+/// リマインドの時刻になったコンテストをリマインドし、次にリマインドする時刻 (UNIX時間) を返します。
+/// リマインドが無効な場合や、予定されているコンテストがない場合は `None` を返します。
+async fn remind_due(ctx: &serenity::Context) -> Result<Option<i64>, Error> {
+    let data = load().unwrap_or_default();
+    if !*data.reminder_enabled.lock().unwrap() {
+        return Ok(None);
+    }
+    let Some(channel) = *data.channel.lock().unwrap() else {
+        return Ok(None);
+    };
+    let before = i64::from(reminder_minutes(&data)) * 60;
+    let upcoming = contests::upcoming_contests(&api::KenkooooApi).await?;
+    let mut reminded = load_reminded();
+    let now = Utc::now().timestamp();
+
+    // 送信に失敗しても、それまでに送ったリマインドは記録してから失敗を返します
+    let mut failed = None;
+    for contest in &upcoming {
+        if contest.start_epoch_second - before > now || reminded.contains(&contest.id) {
+            continue;
+        }
+        let sent = channel
+            .send_message(
+                ctx,
+                notify::new_message(None).embed(reminder_embed(contest, &data)),
+            )
+            .await;
+        if let Err(e) = sent {
+            println!("Failed to send contest reminder {}: {:?}", contest.id, e);
+            failed.get_or_insert(e);
+            continue;
+        }
+        println!("Contest reminder sent: {}", contest.id);
+        reminded.insert(contest.id.clone());
+        save_reminded(&reminded)?;
+    }
+    // 始まったコンテストは二重に通知されることがないので、記録から除きます
+    reminded.retain(|id| upcoming.iter().any(|c| &c.id == id));
+    save_reminded(&reminded)?;
+    if let Some(e) = failed {
+        return Err(e.into());
+    }
+
+    Ok(upcoming
+        .iter()
+        .filter(|c| !reminded.contains(&c.id))
+        .map(|c| c.start_epoch_second - before)
+        .min())
+}
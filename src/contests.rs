@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::api::{self, AtcoderApi};
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// AtCoderのコンテスト一覧のページから読み取った予定を、再取得せずに使う時間
+const SCHEDULE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// これより長いコンテストは、常設のコンテストとみなして除きます
+const PERMANENT_CONTEST_SECONDS: i64 = 365 * 24 * 60 * 60;
+
+/// 一覧に表示するコンテストの数
+const UPCOMING_CONTESTS: usize = 10;
+
+/// AtCoderのコンテスト一覧のページから読み取った予定と、その取得時刻
+static SCHEDULE_CACHE: Mutex<Option<(Instant, Vec<api::ContestItem>)>> = Mutex::new(None);
+
+/// `text` の中の、`start` と `end` に挟まれた最初の部分
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(start)?;
+    Some(rest.split_once(end)?.0)
+}
+
+/// コンテスト一覧のページの、予定されているコンテストの表の1行を読み取ります。
+fn parse_schedule_row(row: &str) -> Option<api::ContestItem> {
+    let cells = row
+        .split("<td")
+        .skip(1)
+        .map(|cell| cell.split_once('>').map_or("", |(_, cell)| cell))
+        .map(|cell| cell.split("</td>").next().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let [start, name, duration, rated] = cells[..] else {
+        return None;
+    };
+    let start = between(start, "<time class='fixtime fixtime-full'>", "</time>")?;
+    let start = DateTime::parse_from_str(start.trim(), "%Y-%m-%d %H:%M:%S%z").ok()?;
+    let (_, link) = name.split_once("<a href=\"/contests/")?;
+    let (id, title) = link.split_once("\">")?;
+    let title = title.split("</a>").next()?;
+    let (hours, minutes) = duration.trim().split_once(':')?;
+    let duration_second = (hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?) * 60;
+    // AtCoder Problemsと同じく、範囲は `~` で表します (` - 1999` なら ` ~ 1999`)
+    let rate_change = match rated.trim() {
+        "-" => "-".to_string(),
+        range => range.replace('-', "~"),
+    };
+    Some(api::ContestItem {
+        id: id.to_string(),
+        start_epoch_second: start.timestamp(),
+        duration_second,
+        title: title.trim().to_string(),
+        rate_change,
+    })
+}
+
+/// AtCoderのコンテスト一覧のページから、予定されているコンテストを読み取ります。
+fn parse_schedule(page: &str) -> Vec<api::ContestItem> {
+    let Some((_, table)) = page.split_once("id=\"contest-table-upcoming\"") else {
+        return vec![];
+    };
+    let table = table.split("</table>").next().unwrap_or_default();
+    table.split("<tr").filter_map(parse_schedule_row).collect()
+}
+
+/// AtCoderのコンテスト一覧のページに載っている、予定されているコンテストを返します。
+/// 最近取得したものがあればキャッシュを返します。
+async fn scheduled_contests(api: &dyn AtcoderApi) -> Result<Vec<api::ContestItem>, Error> {
+    if let Some((fetched_at, contests)) = SCHEDULE_CACHE.lock().unwrap().as_ref() {
+        if fetched_at.elapsed() < SCHEDULE_CACHE_TTL {
+            return Ok(contests.clone());
+        }
+    }
+    let contests = parse_schedule(&api.contests_page().await?);
+    SCHEDULE_CACHE
+        .lock()
+        .unwrap()
+        .replace((Instant::now(), contests.clone()));
+    Ok(contests)
+}
+
+/// AtCoder Problemsのコンテストの一覧に、AtCoderの予定を加えます。
+/// AtCoder Problemsにまだ載っていないコンテストを補い、両方にあるものは、
+/// 開始時刻の変更などが反映されているAtCoderの予定を使います。
+fn merge_schedule(
+    contests: &HashMap<String, api::ContestItem>,
+    scheduled: Vec<api::ContestItem>,
+) -> Vec<api::ContestItem> {
+    let mut contests = contests.clone();
+    contests.extend(scheduled.into_iter().map(|c| (c.id.clone(), c)));
+    contests.into_values().collect()
+}
+
+/// コンテストの一覧を返します。AtCoder Problemsのコンテストは問題データと一緒にキャッシュしたものを使い、
+/// AtCoderのコンテスト一覧のページから予定を補います。ページを取得できなかった場合は、予定を補わずに返します。
+async fn contests(api: &dyn AtcoderApi) -> Result<Vec<api::ContestItem>, Error> {
+    let problem_data = api::problem_data(api).await?;
+    let scheduled = match scheduled_contests(api).await {
+        Ok(scheduled) => scheduled,
+        Err(e) => {
+            println!("Failed to fetch contest schedule: {:?}", e);
+            vec![]
+        }
+    };
+    Ok(merge_schedule(&problem_data.contests, scheduled))
+}
+
+/// 常設のコンテスト (PASTや練習用のコンテストなど) かどうか
+fn is_permanent(contest: &api::ContestItem) -> bool {
+    contest.duration_second >= PERMANENT_CONTEST_SECONDS
+        || contest.id.starts_with("past")
+        || contest.id.contains("practice")
+}
+
+/// これから始まるコンテストを、開始時刻の早い順に返します。常設のコンテストは除きます。
+pub async fn upcoming_contests(api: &dyn AtcoderApi) -> Result<Vec<api::ContestItem>, Error> {
+    let now = Utc::now().timestamp();
+    let mut contests = contests(api)
+        .await?
+        .into_iter()
+        .filter(|c| c.start_epoch_second > now)
+        .filter(|c| !is_permanent(c))
+        .collect::<Vec<_>>();
+    contests.sort_by_key(|c| c.start_epoch_second);
+    Ok(contests)
+}
+
+/// コンテストの種類が一目で分かるように、名前の前に付ける印
+pub fn kind_emoji(contest: &api::ContestItem) -> &'static str {
+    match contest.id.get(..3) {
+        Some("abc") => "🟦",
+        Some("arc") => "🟧",
+        Some("agc") => "🟥",
+        Some("ahc") => "🟩",
+        _ => "⬜",
+    }
+}
+
+/// コンテストのページのURL
+pub fn contest_url(contest: &api::ContestItem) -> String {
+    format!("https://atcoder.jp/contests/{}", contest.id)
+}
+
+/// コンテストの開始時刻 (`2024-01-01 21:00`)
+pub fn start_text(contest: &api::ContestItem, tz: Tz) -> String {
+    DateTime::from_timestamp(contest.start_epoch_second, 0)
+        .unwrap_or_default()
+        .with_timezone(&tz)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+/// コンテストの時間 (`100分`、`4時間` など)
+pub fn duration_text(contest: &api::ContestItem) -> String {
+    let minutes = contest.duration_second / 60;
+    if minutes % 60 == 0 {
+        format!("{}時間", minutes / 60)
+    } else {
+        format!("{}分", minutes)
+    }
+}
+
+/// Ratedの対象 (`Rated: ~ 1999`、`Unrated` など)
+pub fn rated_text(contest: &api::ContestItem) -> String {
+    match contest.rate_change.as_str() {
+        "-" | "" => "Unrated".into(),
+        range => format!("Rated: {}", range),
+    }
+}
+
+/// 開始時刻・時間・Ratedの対象を1行にまとめたもの (`2024-01-01 21:00 (100分) / Rated: ~ 1999` など)
+fn schedule_line(contest: &api::ContestItem, tz: Tz) -> String {
+    format!(
+        "{} ({}) / {}",
+        start_text(contest, tz),
+        duration_text(contest),
+        rated_text(contest)
+    )
+}
+
+/// コンテストの名前にリンクと種類の印を付けたもの
+fn title_line(contest: &api::ContestItem) -> String {
+    format!(
+        "{} [{}]({})",
+        kind_emoji(contest),
+        contest.title,
+        contest_url(contest)
+    )
+}
+
+/// これから始まるコンテストの一覧の埋め込みを作ります。
+pub async fn upcoming_embed(api: &dyn AtcoderApi, tz: Tz) -> Result<CreateEmbed, Error> {
+    let contests = upcoming_contests(api).await?;
+    let lines = contests
+        .iter()
+        .take(UPCOMING_CONTESTS)
+        .map(|contest| format!("{}\n{}", title_line(contest), schedule_line(contest, tz)))
+        .collect::<Vec<_>>();
+    Ok(CreateEmbed::default()
+        .title("予定されているコンテスト")
+        .url(api::ATCODER_CONTESTS_URL)
+        .description(if lines.is_empty() {
+            "予定されているコンテストはありません。".into()
+        } else {
+            lines.join("\n\n")
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use itertools::Itertools;
+
+    /// AtCoderのコンテスト一覧のページの、予定されているコンテストの表
+    const SCHEDULE_PAGE: &str = r#"
+<div id="contest-table-upcoming">
+<h3>予定されたコンテスト</h3>
+<div class="panel panel-default">
+<table class="table table-default table-striped table-hover table-condensed table-bordered small">
+<thead>
+<tr>
+    <th class="text-center" width="20%">開始時刻</th>
+    <th class="text-center">コンテスト名</th>
+    <th class="text-center" width="10%">時間</th>
+    <th class="text-center" width="12%">Rated対象</th>
+</tr>
+</thead>
+<tbody>
+<tr>
+    <td class="text-center"><a href='http://www.timeanddate.com/worldclock/fixedtime.html?iso=20241019T2100&p1=248' target='blank'><time class='fixtime fixtime-full'>2024-10-19 21:00:00+0900</time></a></td>
+    <td ><span aria-hidden='true' data-toggle='tooltip' data-placement='top' title="アルゴリズム">Ⓐ</span> <span class="user-blue">◉</span> <a href="/contests/abc376">AtCoder Beginner Contest 376</a></td>
+    <td class="text-center">01:40</td>
+    <td class="text-center"> - 1999</td>
+</tr>
+<tr>
+    <td class="text-center"><a href='http://www.timeanddate.com/worldclock/fixedtime.html?iso=20241027T2100&p1=248' target='blank'><time class='fixtime fixtime-full'>2024-10-27 21:00:00+0900</time></a></td>
+    <td ><span aria-hidden='true' data-toggle='tooltip' data-placement='top' title="アルゴリズム">Ⓐ</span> <span class="user-red">◉</span> <a href="/contests/agc069">AtCoder Grand Contest 069</a></td>
+    <td class="text-center">03:00</td>
+    <td class="text-center">1200 - </td>
+</tr>
+</tbody>
+</table>
+</div>
+</div>
+<div id="contest-table-recent">
+</div>
+"#;
+
+    #[test]
+    fn schedule_is_read_from_contests_page() {
+        let contests = parse_schedule(SCHEDULE_PAGE);
+        assert_eq!(contests.len(), 2);
+        assert_eq!(contests[0].id, "abc376");
+        assert_eq!(contests[0].title, "AtCoder Beginner Contest 376");
+        assert_eq!(contests[0].start_epoch_second, 1729339200);
+        assert_eq!(contests[0].duration_second, 100 * 60);
+        assert_eq!(rated_text(&contests[0]), "Rated: ~ 1999");
+        assert_eq!(contests[1].id, "agc069");
+        assert_eq!(contests[1].duration_second, 3 * 60 * 60);
+        assert_eq!(contests[1].rate_change, "1200 ~");
+        assert!(parse_schedule("<html></html>").is_empty());
+    }
+
+    #[test]
+    fn schedule_adds_and_updates_contests() {
+        let known = [
+            fixtures::contest("abc375", 100, 6000),
+            fixtures::contest("abc376", 200, 6000),
+        ]
+        .into_iter()
+        .map(|c| (c.id.clone(), c))
+        .collect();
+        let scheduled = vec![
+            fixtures::contest("abc376", 300, 6000),
+            fixtures::contest("abc377", 400, 6000),
+        ];
+        let contests = merge_schedule(&known, scheduled)
+            .into_iter()
+            .map(|c| (c.id, c.start_epoch_second))
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            contests,
+            [
+                ("abc375".to_string(), 100),
+                ("abc376".to_string(), 300),
+                ("abc377".to_string(), 400)
+            ]
+        );
+    }
+}
@@ -1,30 +1,348 @@
-use crate::notify;
-use chrono::{Duration, Local, NaiveTime};
+use std::{future::Future, sync::Mutex};
+
+use crate::{api, load, notify, Data};
+use anyhow::Error;
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use poise::serenity_prelude as serenity;
-use tokio::time::{sleep_until, Instant};
+use tokio::{
+    sync::Notify,
+    time::{sleep, sleep_until, Instant},
+};
+
+/// レート制限で通知を取りやめたとき、再実行するまでの待ち時間
+const RATE_LIMIT_RESCHEDULE: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+/// レート制限で通知を取りやめたとき、再実行する回数
+const RATE_LIMIT_RESCHEDULES: u32 = 3;
+
+/// 定期通知を行う時刻の既定値。環境変数 `DAILY_HOUR` で変更できます。
+const DEFAULT_DAILY_HOUR: u32 = 4;
+
+/// 定期通知を行う時刻 (時)
+fn daily_hour() -> u32 {
+    std::env::var("DAILY_HOUR")
+        .ok()
+        .and_then(|hour| hour.parse().ok())
+        .filter(|&hour| hour < 24)
+        .unwrap_or(DEFAULT_DAILY_HOUR)
+}
+
+/// 定期通知を行う時刻。`/config schedule` で設定されていなければ、`DAILY_HOUR` 時ちょうどです。
+pub fn report_time(data: &Data) -> NaiveTime {
+    data.report_time
+        .lock()
+        .unwrap()
+        .unwrap_or(NaiveTime::from_hms_opt(daily_hour(), 0, 0).unwrap())
+}
+
+/// 通知の時刻が変更されたことを、次の通知を待っているタスクに知らせます
+static RESCHEDULE: Notify = Notify::const_new();
+
+/// 次の通知の時刻を、現在の設定で求め直させます。
+pub fn reschedule() {
+    RESCHEDULE.notify_one();
+}
+
+/// 次に定期通知を行う予定の時刻
+static NEXT_RUN: Mutex<Option<DateTime<Utc>>> = Mutex::new(None);
+
+/// 通知を実行した時刻と、その結果 (失敗した場合はエラーの内容)
+pub type RunOutcome = (DateTime<Utc>, Result<(), String>);
+
+/// 最後に通知を実行した結果
+static LAST_RUN: Mutex<Option<RunOutcome>> = Mutex::new(None);
+
+/// 次に定期通知を行う予定の時刻を返します。
+pub fn next_run() -> Option<DateTime<Utc>> {
+    *NEXT_RUN.lock().unwrap()
+}
+
+/// 最後に通知を実行した時刻と、その結果を返します。
+pub fn last_run() -> Option<RunOutcome> {
+    LAST_RUN.lock().unwrap().clone()
+}
+
+/// 通知を実行した結果を記録します。
+pub fn record_outcome(result: Result<(), String>) {
+    LAST_RUN.lock().unwrap().replace((Utc::now(), result));
+}
+
+/// `interval` 日ごとの通知で、`date` が通知する日かどうか。
+/// 日付だけから決まるので、再起動しても通知する日はずれません。
+fn is_run_day(date: NaiveDate, interval: u32) -> bool {
+    (date.num_days_from_ce() as u32).is_multiple_of(interval)
+}
+
+/// 夏時間の切り替えなどで飛ばされる時間の最大値 (分)
+const MAX_SKIPPED_MINUTES: i64 = 24 * 60;
+
+/// `timezone` での `date` の `time` の時刻を返します。
+/// 夏時間の切り替えでその時刻が存在しない日は、存在するようになった直後の時刻を、
+/// 2回ある日は早い方を返します。
+fn local_time(timezone: Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+    let local = date.and_time(time);
+    (0..=MAX_SKIPPED_MINUTES)
+        .find_map(|minutes| {
+            timezone
+                .from_local_datetime(&(local + Duration::minutes(minutes)))
+                .earliest()
+        })
+        .unwrap_or_else(|| timezone.from_utc_datetime(&local))
+}
+
+/// 夏時間の切り替えで、設定した時刻どおりに通知できない日
+#[derive(Debug, PartialEq)]
+pub enum TimeConflict {
+    /// 時刻が存在しない日と、代わりに通知する時刻
+    Skipped(NaiveDate, DateTime<Tz>),
+    /// 時刻が2回ある日。1回目に通知します
+    Repeated(NaiveDate),
+}
+
+/// `today` から1年の間で、夏時間の切り替えで `time` が存在しないか、2回ある日を返します。
+pub fn time_conflicts(timezone: Tz, time: NaiveTime, today: NaiveDate) -> Vec<TimeConflict> {
+    today
+        .iter_days()
+        .take(366)
+        .filter_map(
+            |date| match timezone.from_local_datetime(&date.and_time(time)) {
+                LocalResult::Single(_) => None,
+                LocalResult::Ambiguous(..) => Some(TimeConflict::Repeated(date)),
+                LocalResult::None => Some(TimeConflict::Skipped(
+                    date,
+                    local_time(timezone, date, time),
+                )),
+            },
+        )
+        .collect()
+}
+
+/// `now` 以降で、最初に通知する時刻を返します。
+/// 日付ごとに時刻を求めるので、夏時間の切り替えをまたいでも通知する時刻はずれません。
+fn next_run_time(now: DateTime<Tz>, time: NaiveTime, interval: u32) -> DateTime<Tz> {
+    now.date_naive()
+        .iter_days()
+        .filter(|&date| is_run_day(date, interval))
+        .map(|date| local_time(now.timezone(), date, time))
+        .find(|target| *target >= now)
+        .unwrap()
+}
 
 pub async fn wait(ctx: serenity::Context) {
     loop {
-        let now = Local::now();
-        let target_time = {
-            let res = Local::now()
-                .with_time(NaiveTime::from_hms_opt(4, 0, 0).unwrap())
-                .unwrap();
-            if res < now {
-                res + Duration::days(1)
-            } else {
-                res
-            }
-        };
+        let data = load().unwrap_or_default();
+        let (timezone, interval) = (data.timezone(), data.interval());
+        let now = Utc::now().with_timezone(&timezone);
+        let target_time = next_run_time(now, report_time(&data), interval);
         let sleep_duration = target_time - now;
+        NEXT_RUN
+            .lock()
+            .unwrap()
+            .replace(target_time.with_timezone(&Utc));
 
         println!("Now: {}", now);
         println!("Next run: {}", target_time);
         println!("Sleeping for {} seconds", sleep_duration.num_seconds());
 
-        sleep_until(Instant::now() + sleep_duration.to_std().unwrap()).await;
-        notify::notify(ctx.clone())
-            .await
-            .expect("Failed to run daily job");
+        tokio::select! {
+            _ = sleep_until(Instant::now() + sleep_duration.to_std().unwrap()) => {}
+            _ = RESCHEDULE.notified() => {
+                println!("Schedule changed, rescheduling");
+                continue;
+            }
+        }
+        // 待っている間に間隔が変更された場合は、新しい間隔で次の通知日を求め直します
+        let current = load().map(|data| data.interval()).unwrap_or(1);
+        if current != interval {
+            println!(
+                "Interval changed ({} -> {}), rescheduling",
+                interval, current
+            );
+            continue;
+        }
+        run(&ctx, interval).await;
+    }
+}
+
+/// 直近 `days` 日間の通知を実行します。一時停止している場合は何もしません。
+///
+/// `ctx` のHTTPクライアントはゲートウェイの接続とは独立しているため、
+/// 再接続の後も同じ `ctx` で投稿できます。
+async fn run(ctx: &serenity::Context, days: u32) {
+    if load().is_ok_and(|data| *data.paused.lock().unwrap()) {
+        println!("Daily job skipped: paused");
+        return;
+    }
+    let ctx = ctx.clone();
+    record_outcome(run_job(|| notify::notify(ctx.clone(), days)).await);
+}
+
+/// `job` を実行し、その結果を返します。レート制限を受けた場合は、時間をおいて再実行します。
+/// 失敗やパニックはログに残して結果として返すだけにして、翌日以降の実行は続けられるようにします。
+async fn run_job<F, Fut>(job: F) -> Result<(), String>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<(), Error>> + Send + 'static,
+{
+    for _ in 0..RATE_LIMIT_RESCHEDULES {
+        // 通知の途中でパニックしても次回以降の実行を続けられるよう、別のタスクで実行します
+        let result = match tokio::spawn(job()).await {
+            Ok(result) => result,
+            Err(e) => {
+                println!("Daily job panicked: {:?}", e);
+                return Err("panicked".into());
+            }
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e) if e.is::<api::RateLimited>() => {
+                println!(
+                    "Daily job skipped due to rate limiting, rescheduled in {:?}",
+                    RATE_LIMIT_RESCHEDULE
+                );
+                sleep(RATE_LIMIT_RESCHEDULE).await;
+            }
+            Err(e) => {
+                println!("Failed to run daily job: {:?}", e);
+                return Err(e.to_string());
+            }
+        }
+    }
+    println!("Daily job skipped: still rate limited after rescheduling");
+    Err("rate limited".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+    use std::{
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn nonexistent_time_runs_right_after_the_gap() {
+        // 2024-03-10 02:00〜03:00 (America/New_York) は夏時間の開始で存在しません
+        let now = New_York.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap();
+        assert_eq!(
+            next_run_time(now, time(2, 30), 1),
+            New_York.with_ymd_and_hms(2024, 3, 10, 3, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn ambiguous_time_runs_once_at_the_earliest() {
+        // 2024-11-03 01:00〜02:00 (America/New_York) は夏時間の終了で2回あります
+        let now = New_York.with_ymd_and_hms(2024, 11, 3, 0, 0, 0).unwrap();
+        let first = next_run_time(now, time(1, 30), 1);
+        assert_eq!(
+            first.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap()
+        );
+        let next = next_run_time(first + Duration::seconds(1), time(1, 30), 1);
+        assert_eq!(
+            next.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 11, 4).unwrap()
+        );
+    }
+
+    #[test]
+    fn local_time_is_kept_across_dst() {
+        let now = New_York.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap();
+        let next = next_run_time(now, time(4, 0), 1);
+        assert_eq!(
+            next,
+            New_York.with_ymd_and_hms(2024, 3, 10, 4, 0, 0).unwrap()
+        );
+        assert_eq!(next - now, Duration::hours(15));
+    }
+
+    #[test]
+    fn interval_skips_to_the_next_run_day() {
+        let now = Tz::Asia__Tokyo
+            .with_ymd_and_hms(2024, 1, 1, 12, 0, 0)
+            .unwrap();
+        let next = next_run_time(now, time(4, 0), 3);
+        assert!(next > now);
+        assert!(is_run_day(next.date_naive(), 3));
+        assert!(next - now < Duration::days(3));
+    }
+
+    type JobFuture = Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+    /// 呼ばれた回数を数えながら、`results` の結果を順に返す通知の代わり
+    fn stub_job(
+        results: Vec<fn() -> Result<(), Error>>,
+    ) -> (impl Fn() -> JobFuture, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let job = move || {
+            let i = counter.fetch_add(1, Ordering::Relaxed);
+            let result = results[i.min(results.len() - 1)];
+            Box::pin(async move { result() }) as JobFuture
+        };
+        (job, calls)
+    }
+
+    fn rate_limited() -> Result<(), Error> {
+        Err(Error::msg(api::RateLimited { retry_after: None }))
+    }
+
+    #[tokio::test]
+    async fn failures_and_panics_are_reported_without_stopping() {
+        let (job, calls) = stub_job(vec![|| panic!("unexpected")]);
+        assert_eq!(run_job(&job).await, Err("panicked".into()));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        let (job, calls) = stub_job(vec![|| Err(Error::msg("broken")), || Ok(())]);
+        assert_eq!(run_job(&job).await, Err("broken".into()));
+        // 失敗した後も、次の実行は行えます
+        assert_eq!(run_job(&job).await, Ok(()));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_job_is_rescheduled() {
+        let (job, calls) = stub_job(vec![rate_limited, || Ok(())]);
+        let start = Instant::now();
+        assert_eq!(run_job(&job).await, Ok(()));
+        assert_eq!(Instant::now() - start, RATE_LIMIT_RESCHEDULE);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        let (job, calls) = stub_job(vec![rate_limited]);
+        assert_eq!(run_job(&job).await, Err("rate limited".into()));
+        assert_eq!(
+            calls.load(Ordering::Relaxed),
+            RATE_LIMIT_RESCHEDULES as usize
+        );
+    }
+
+    #[test]
+    fn conflicting_times_are_found() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(
+            time_conflicts(New_York, time(2, 30), today),
+            [TimeConflict::Skipped(
+                NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(),
+                New_York.with_ymd_and_hms(2024, 3, 10, 3, 0, 0).unwrap()
+            )]
+        );
+        assert_eq!(
+            time_conflicts(New_York, time(1, 30), today),
+            [TimeConflict::Repeated(
+                NaiveDate::from_ymd_opt(2024, 11, 3).unwrap()
+            )]
+        );
+        assert!(time_conflicts(New_York, time(4, 0), today).is_empty());
+        assert!(time_conflicts(Tz::Asia__Tokyo, time(2, 30), today).is_empty());
     }
 }
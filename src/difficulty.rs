@@ -1,22 +1,63 @@
 use std::fmt::Display;
 
-#[derive(PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// 難易度の色。大小関係は色の強さの順で、埋め込みの色を `max` で選ぶのに使われるため、
+/// 判別値は弱い順に明示しています。
+#[derive(PartialEq, PartialOrd, Eq, Ord, Hash, Clone, Copy, Debug)]
 pub enum Color {
-    Black, // for unknown difficulty
-    Gray,
-    Brown,
-    Green,
-    Cyan,
-    Blue,
-    Yellow,
-    Orange,
-    Red,
+    Black = 0, // for unknown difficulty
+    Gray = 1,
+    Brown = 2,
+    Green = 3,
+    Cyan = 4,
+    Blue = 5,
+    Yellow = 6,
+    Orange = 7,
+    Red = 8,
+}
+
+/// 難易度が不明な問題だけの埋め込みの色。黒 (0x000000) は埋め込みの色が無いものとして
+/// 表示されてしまうため、明るい灰色にしています。
+const UNKNOWN_EMBED_COLOR: u32 = 0xcccccc;
+
+impl Color {
+    /// 英語での色の名前
+    pub fn english_name(self) -> &'static str {
+        match self {
+            Color::Black => "Unknown",
+            Color::Gray => "Gray",
+            Color::Brown => "Brown",
+            Color::Green => "Green",
+            Color::Cyan => "Cyan",
+            Color::Blue => "Blue",
+            Color::Yellow => "Yellow",
+            Color::Orange => "Orange",
+            Color::Red => "Red",
+        }
+    }
+
+    /// 色を表す絵文字。難易度が不明な場合は黒い四角です。
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Color::Black => "⬛",
+            Color::Gray => "⬜",
+            Color::Brown => "🟫",
+            Color::Green => "🟩",
+            Color::Cyan => "💠",
+            Color::Blue => "🟦",
+            Color::Yellow => "🟨",
+            Color::Orange => "🟧",
+            Color::Red => "🟥",
+        }
+    }
 }
 
 impl From<Color> for u32 {
     fn from(val: Color) -> Self {
         match val {
-            Color::Black => 0x000000,
+            Color::Black => UNKNOWN_EMBED_COLOR,
             Color::Gray => 0x808080,
             Color::Brown => 0x804000,
             Color::Green => 0x008000,
@@ -32,7 +73,7 @@ impl From<Color> for u32 {
 impl From<Color> for String {
     fn from(val: Color) -> Self {
         match val {
-            Color::Black => unreachable!(),
+            Color::Black => "不明",
             Color::Gray => "灰",
             Color::Brown => "茶",
             Color::Green => "緑",
@@ -53,24 +94,159 @@ impl Display for Color {
 }
 
 impl From<u32> for Color {
+    /// AtCoderの標準の境界で色を求めます。
     fn from(val: u32) -> Self {
-        match val {
-            0..400 => Color::Gray,
-            400..800 => Color::Brown,
-            800..1200 => Color::Green,
-            1200..1600 => Color::Cyan,
-            1600..2000 => Color::Blue,
-            2000..2400 => Color::Yellow,
-            2400..2800 => Color::Orange,
-            2800.. => Color::Red,
-        }
+        ColorBands::default().color(val)
+    }
+}
+
+/// 灰色より上の色の数
+const BAND_COUNT: usize = 7;
+
+/// 難易度の低い順に並べた色
+const COLORS: [Color; BAND_COUNT + 1] = [
+    Color::Gray,
+    Color::Brown,
+    Color::Green,
+    Color::Cyan,
+    Color::Blue,
+    Color::Yellow,
+    Color::Orange,
+    Color::Red,
+];
+
+/// 難易度の色の境界。茶色から赤までの、それぞれの色になる最小の難易度です。
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ColorBands([u32; BAND_COUNT]);
+
+impl Default for ColorBands {
+    /// AtCoderの標準の境界 (400刻み)
+    fn default() -> Self {
+        ColorBands([400, 800, 1200, 1600, 2000, 2400, 2800])
+    }
+}
+
+impl ColorBands {
+    /// 境界を作ります。数が合わないか、境界が0より大きく狭義単調増加でない場合は `None` を返します。
+    pub fn new(bounds: &[u32]) -> Option<Self> {
+        let bounds: [u32; BAND_COUNT] = bounds.try_into().ok()?;
+        let increasing = bounds[0] > 0 && bounds.windows(2).all(|w| w[0] < w[1]);
+        increasing.then_some(ColorBands(bounds))
+    }
+
+    /// 正規化した難易度から色を求めます。
+    pub fn color(&self, difficulty: u32) -> Color {
+        COLORS[self.0.iter().filter(|&&bound| bound <= difficulty).count()]
+    }
+
+    /// 色ごとの難易度の範囲 (下限と、上限があれば上限)。灰色から順に並びます。
+    pub fn ranges(&self) -> Vec<(Color, u32, Option<u32>)> {
+        let lower = std::iter::once(0).chain(self.0);
+        let upper = self.0.iter().map(|&bound| Some(bound - 1)).chain([None]);
+        COLORS
+            .into_iter()
+            .zip(lower.zip(upper))
+            .map(|(color, (lower, upper))| (color, lower, upper))
+            .collect()
+    }
+
+    /// APIから取得した生の難易度から色を求めます。
+    pub fn difficulty_color(&self, difficulty: i64) -> Color {
+        self.color(normalize(difficulty))
     }
 }
 
+impl Display for ColorBands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join(", "))
+    }
+}
+
+/// 難易度を表示用に補正します。400未満の難易度は、AtCoder Problemsと同じく
+/// `400 / exp(1 - d / 400)` で正の値に丸めます。この式は400で400になるため、境界で値が飛びません
+/// (399は399、0は147になります)。
 pub fn normalize(difficulty: i64) -> u32 {
     if difficulty >= 400 {
         difficulty as u32
     } else {
-        (400.0 / (1.0 + (1.0 - difficulty as f64 / 400.0).exp())) as u32
+        (400.0 / (1.0 - difficulty as f64 / 400.0).exp()).round() as u32
+    }
+}
+
+/// 埋め込みの色の決め方
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum EmbedColor {
+    /// 解いた問題の最高難易度の色
+    #[default]
+    Difficulty,
+    /// 固定の色
+    Fixed(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_ordered_by_strength() {
+        assert!(Color::Red > Color::Gray);
+        assert!(Color::Gray > Color::Black);
+        assert!(COLORS.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(COLORS.into_iter().max(), Some(Color::Red));
+    }
+
+    #[test]
+    fn unknown_color_has_a_name() {
+        assert_eq!(Color::Black.to_string(), "不明");
+        assert_eq!(Color::Red.to_string(), "赤");
+    }
+
+    #[test]
+    fn custom_bands_change_colors() {
+        let bands = ColorBands::new(&[100, 200, 300, 1000, 2000, 3000, 4000]).unwrap();
+        assert_eq!(bands.color(99), Color::Gray);
+        assert_eq!(bands.color(100), Color::Brown);
+        assert_eq!(bands.color(999), Color::Cyan);
+        assert_eq!(bands.color(4000), Color::Red);
+        assert_eq!(ColorBands::default().color(999), Color::Green);
+        assert_eq!(
+            bands.ranges()[..2],
+            [(Color::Gray, 0, Some(99)), (Color::Brown, 100, Some(199))]
+        );
+    }
+
+    #[test]
+    fn bands_must_be_increasing() {
+        assert_eq!(
+            ColorBands::new(&[400, 800, 1200, 1600, 2000, 2400, 2800]),
+            Some(ColorBands::default())
+        );
+        // 減少している・重複している・0から始まる・数が合わない境界は受け付けません
+        assert_eq!(
+            ColorBands::new(&[400, 800, 700, 1600, 2000, 2400, 2800]),
+            None
+        );
+        assert_eq!(
+            ColorBands::new(&[400, 800, 800, 1600, 2000, 2400, 2800]),
+            None
+        );
+        assert_eq!(
+            ColorBands::new(&[0, 800, 1200, 1600, 2000, 2400, 2800]),
+            None
+        );
+        assert_eq!(ColorBands::new(&[400, 800, 1200]), None);
+    }
+
+    #[test]
+    fn normalize_is_continuous_at_400() {
+        // 400以上はそのまま
+        assert_eq!(normalize(400), 400);
+        assert_eq!(normalize(401), 401);
+        assert_eq!(normalize(2800), 2800);
+        // 400未満は正の値に丸めます
+        assert_eq!(normalize(399), 399);
+        assert_eq!(normalize(0), 147);
+        assert_eq!(normalize(-1000), 12);
+        assert!((-2000..400).all(|d| normalize(d) <= normalize(d + 1)));
     }
 }
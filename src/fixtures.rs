@@ -0,0 +1,78 @@
+//! テストで使う、AtCoder ProblemsのAPIが返すものと同じ形のデータ
+
+use std::collections::HashMap;
+
+use crate::api::{
+    ContestItem, JudgeStatus, ProblemData, ProblemItem, ProblemModelItem, SubmissionItem,
+};
+use chrono::Local;
+
+/// 問題ID (`abc300_a`) から、コンテストIDと問題番号を決めた問題を作ります。
+pub fn problem(id: &str, title: &str) -> ProblemItem {
+    let (contest_id, index) = id.rsplit_once('_').unwrap();
+    ProblemItem {
+        id: id.into(),
+        contest_id: contest_id.into(),
+        problem_index: index.to_uppercase(),
+        name: title.into(),
+        title: format!("{}. {}", index.to_uppercase(), title),
+    }
+}
+
+/// `start_epoch_second` から `duration_second` 秒間開催されるコンテストを作ります。
+pub fn contest(id: &str, start_epoch_second: i64, duration_second: i64) -> ContestItem {
+    ContestItem {
+        id: id.into(),
+        start_epoch_second,
+        duration_second,
+        title: id.to_uppercase(),
+        rate_change: " ~ 1999".into(),
+    }
+}
+
+/// `user` が `problem_id` の問題に `epoch_second` に提出した、Rustの提出を作ります。
+pub fn submission(
+    id: i64,
+    user: &str,
+    problem_id: &str,
+    epoch_second: i64,
+    result: JudgeStatus,
+) -> SubmissionItem {
+    SubmissionItem {
+        id,
+        epoch_second,
+        problem_id: problem_id.into(),
+        contest_id: problem_id.rsplit_once('_').unwrap().0.into(),
+        user_id: user.into(),
+        language: "Rust (rustc 1.70.0)".into(),
+        point: 100.0,
+        length: 1000,
+        result,
+        execution_time: Some(1),
+    }
+}
+
+/// 問題・難易度・コンテストから問題データを作ります。
+pub fn problem_data(
+    problems: Vec<ProblemItem>,
+    difficulties: &[(&str, i64)],
+    contests: Vec<ContestItem>,
+) -> ProblemData {
+    ProblemData {
+        problems: problems.into_iter().map(|p| (p.id.clone(), p)).collect(),
+        problem_models: difficulties
+            .iter()
+            .map(|&(id, difficulty)| {
+                (
+                    id.to_string(),
+                    ProblemModelItem {
+                        difficulty: Some(difficulty),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>(),
+        contests: contests.into_iter().map(|c| (c.id.clone(), c)).collect(),
+        fetched_at: Local::now(),
+    }
+}
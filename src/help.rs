@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use crate::Data;
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+type Command = poise::Command<Arc<Data>, Error>;
+
+/// グループに属さないコマンドのページの見出し
+const OTHER_COMMANDS_TITLE: &str = "その他のコマンド";
+
+/// `/config channel` のような、実際に入力するコマンドの名前とコマンドの組を、サブコマンドまで展開して返します。
+fn leaf_commands<'a>(prefix: &str, commands: &'a [Command]) -> Vec<(String, &'a Command)> {
+    commands
+        .iter()
+        .filter(|command| !command.hide_in_help)
+        .flat_map(|command| {
+            let path = format!("{}{}", prefix, command.name);
+            if command.subcommands.is_empty() {
+                vec![(path, command)]
+            } else {
+                leaf_commands(&format!("{} ", path), &command.subcommands)
+            }
+        })
+        .collect()
+}
+
+/// 引数を含めたコマンドの書き方 (`/report run [preview] [days]` など)。省略できる引数は `[]` で囲みます。
+fn usage(path: &str, command: &Command) -> String {
+    let mut usage = format!("/{}", path);
+    for parameter in &command.parameters {
+        if parameter.required {
+            usage.push_str(&format!(" <{}>", parameter.name));
+        } else {
+            usage.push_str(&format!(" [{}]", parameter.name));
+        }
+    }
+    usage
+}
+
+/// 必須の引数だけを指定した、コマンドの入力例
+fn example(path: &str, command: &Command) -> String {
+    let mut example = format!("/{}", path);
+    for parameter in command.parameters.iter().filter(|p| p.required) {
+        let value = parameter
+            .choices
+            .first()
+            .map_or(format!("<{}>", parameter.name), |choice| {
+                choice.name.clone()
+            });
+        example.push_str(&format!(" {}:{}", parameter.name, value));
+    }
+    example
+}
+
+fn description(command: &Command) -> &str {
+    command.description.as_deref().unwrap_or("")
+}
+
+/// コマンドの一覧を、グループごとに1ページずつの埋め込みにします。
+/// グループに属さないコマンドは、最後のページにまとめます。
+pub fn overview_embeds(commands: &[Command]) -> Vec<CreateEmbed> {
+    // トップレベルでも使えるようにしたコマンドは、グループの中にだけ表示します
+    let grouped = commands
+        .iter()
+        .flat_map(|command| &command.subcommands)
+        .map(|command| command.source_code_name.as_str())
+        .collect::<Vec<_>>();
+    let mut pages = vec![];
+    let mut others = vec![];
+    for command in commands
+        .iter()
+        .filter(|command| !command.hide_in_help)
+        .filter(|command| !grouped.contains(&command.source_code_name.as_str()))
+    {
+        if command.subcommands.is_empty() {
+            others.push(format!(
+                "`{}` {}",
+                usage(&command.name, command),
+                description(command)
+            ));
+            continue;
+        }
+        let lines = leaf_commands(&format!("{} ", command.name), &command.subcommands)
+            .into_iter()
+            .map(|(path, command)| format!("`{}` {}", usage(&path, command), description(command)))
+            .collect::<Vec<_>>();
+        pages.push(
+            CreateEmbed::default()
+                .title(format!("/{} — {}", command.name, description(command)))
+                .description(lines.join("\n")),
+        );
+    }
+    if !others.is_empty() {
+        pages.push(
+            CreateEmbed::default()
+                .title(OTHER_COMMANDS_TITLE)
+                .description(others.join("\n")),
+        );
+    }
+    pages
+}
+
+/// 1つのコマンドの詳しい説明の埋め込みを作ります。
+/// `query` は `config channel` のような名前か、最後の部分 (`channel`) だけでも指定できます。
+/// 見つからない場合は `None` を返します。
+pub fn command_embed(commands: &[Command], query: &str) -> Option<CreateEmbed> {
+    let query = query.trim().trim_start_matches('/').to_lowercase();
+    let leaves = leaf_commands("", commands);
+    let (path, command) = leaves
+        .iter()
+        .find(|(path, _)| *path == query)
+        .or_else(|| leaves.iter().find(|(_, command)| command.name == query))?;
+
+    let mut text = description(command).to_string();
+    if let Some(help_text) = &command.help_text {
+        text.push_str(&format!("\n\n{}", help_text));
+    }
+    let mut embed = CreateEmbed::default()
+        .title(format!("`{}`", usage(path, command)))
+        .description(text);
+    if !command.parameters.is_empty() {
+        let parameters = command
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "`{}` ({}) {}",
+                    parameter.name,
+                    if parameter.required {
+                        "必須"
+                    } else {
+                        "省略可"
+                    },
+                    parameter.description.as_deref().unwrap_or("")
+                )
+            })
+            .collect::<Vec<_>>();
+        embed = embed.field("引数", parameters.join("\n"), false);
+    }
+    embed = embed.field("例", format!("`{}`", example(path, command)), false);
+    if command.owners_only {
+        embed = embed.field("権限", "Botの管理者のみ", false);
+    } else if command.required_permissions.administrator() {
+        embed = embed.field("権限", "サーバーの管理者のみ", false);
+    }
+    Some(embed)
+}
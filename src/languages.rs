@@ -0,0 +1,92 @@
+use crate::{
+    api::{self, AtcoderApi, JudgeStatus},
+    notify,
+};
+use anyhow::Error;
+use chrono_tz::Tz;
+use itertools::Itertools;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// 表示する言語の数
+const TOP_LANGUAGES: usize = 10;
+
+/// 言語名から版やコンパイラの情報を取り除きます (`C++ 20 (gcc 12.2)` なら `C++`)。
+/// PyPyはPythonとは別の言語として扱います。
+fn normalize_language(language: &str) -> String {
+    let (name, detail) = language.split_once(" (").unwrap_or((language, ""));
+    if name.starts_with("PyPy") || detail.starts_with("PyPy") {
+        return "PyPy".into();
+    }
+    let trimmed = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == ' ' || c == '.');
+    if trimmed.is_empty() {
+        name.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// ユーザーのACした提出を言語ごとに数えた埋め込みを作ります。
+/// `days` を指定した場合は、直近 `days` 日間の提出だけを数えます。ACが1つもない場合は `None` を返します。
+pub async fn language_stats_embed(
+    api: &dyn AtcoderApi,
+    user: &str,
+    days: Option<u32>,
+    tz: Tz,
+) -> Result<Option<CreateEmbed>, Error> {
+    let from = days.map_or(0, |days| notify::start_of_day(tz, days.into()).timestamp());
+    let submissions = api::fetch_submission_history(api, user, from).await?;
+    let counts = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .map(|s| normalize_language(&s.language))
+        .counts();
+    let total = counts.values().sum::<usize>();
+    if total == 0 {
+        return Ok(None);
+    }
+
+    let lines = counts
+        .into_iter()
+        .sorted_by(|(a_lang, a), (b_lang, b)| b.cmp(a).then(a_lang.cmp(b_lang)))
+        .take(TOP_LANGUAGES)
+        .map(|(language, count)| {
+            format!(
+                "{} — {} 回 ({:.1}%)",
+                language,
+                count,
+                count as f64 * 100.0 / total as f64
+            )
+        })
+        .join("\n");
+    Ok(Some(
+        CreateEmbed::default()
+            .title(format!(
+                "{} さんの言語別AC数 ({})",
+                submissions.first().map_or(user, |s| s.user_id.as_str()),
+                match days {
+                    Some(days) => format!("過去{}日間", days),
+                    None => "全期間".into(),
+                }
+            ))
+            .url(api::user_page_url(user))
+            .description(lines),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_stripped_from_language_names() {
+        assert_eq!(normalize_language("C++ 20 (gcc 12.2)"), "C++");
+        assert_eq!(normalize_language("Python (CPython 3.11.4)"), "Python");
+        // PyPyはPythonとは別の言語として数えます
+        assert_eq!(normalize_language("Python (PyPy 3.10-v7.3.12)"), "PyPy");
+        assert_eq!(normalize_language("PyPy3 (7.3.0)"), "PyPy");
+        assert_eq!(normalize_language("C# 11.0 (.NET 7.0.7)"), "C#");
+        assert_eq!(normalize_language("Rust (rustc 1.70.0)"), "Rust");
+        assert_eq!(normalize_language("Bash"), "Bash");
+    }
+}
@@ -1,84 +1,530 @@
-use std::{collections::BTreeSet, fs, sync::Mutex};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    sync::{Arc, Mutex},
+};
 
-use anyhow::Error;
+use anyhow::{bail, Context as _, Error};
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
 use dotenvy::dotenv;
 use poise::serenity_prelude as serenity;
 use serde::{Deserialize, Serialize};
 
+mod activity;
+mod api;
+mod bot_config;
 mod commands;
+mod compare;
+mod contest_reminder;
+mod contests;
 mod daily_job;
 mod difficulty;
+#[cfg(test)]
+mod fixtures;
+mod help;
+mod languages;
+mod messages;
+mod metrics;
+#[cfg(test)]
+mod mock_server;
 mod notify;
+mod paginate;
+mod problem;
+mod random;
+mod rating;
+mod stats;
+mod streak;
+mod suggest;
+mod virtual_contest;
 
-type Context<'a> = poise::Context<'a, Data, Error>;
+type Context<'a> = poise::Context<'a, Arc<Data>, Error>;
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 struct Data {
+    #[serde(default)]
     channel: Mutex<Option<serenity::ChannelId>>,
+    #[serde(default)]
+    guild: Mutex<Option<serenity::GuildId>>,
+    #[serde(default)]
     users: Mutex<BTreeSet<String>>,
+    #[serde(default)]
+    first_ac_only: Mutex<bool>,
+    /// AtCoderのユーザー名ごとの、紐付けられたDiscordアカウント。通知でメンションします
+    #[serde(default)]
+    links: Mutex<BTreeMap<String, serenity::UserId>>,
+    #[serde(default)]
+    dm: Mutex<BTreeSet<serenity::UserId>>,
+    #[serde(default)]
+    embed_color: Mutex<difficulty::EmbedColor>,
+    #[serde(default)]
+    role: Mutex<Option<serenity::RoleId>>,
+    #[serde(default)]
+    group_by_contest: Mutex<bool>,
+    #[serde(default)]
+    goals: Mutex<BTreeMap<String, u32>>,
+    /// 日付の区切りや時刻の表示に使うタイムゾーン。`None` のときは [`DEFAULT_TIMEZONE`]
+    #[serde(default)]
+    timezone: Mutex<Option<Tz>>,
+    #[serde(default)]
+    post_format: Mutex<notify::PostFormat>,
+    /// 誰もACしなかった日に、登録されているユーザーに呼びかけるかどうか
+    #[serde(default)]
+    nudge_idle: Mutex<bool>,
+    /// ACできなかった問題も「挑戦」として通知するかどうか
+    #[serde(default)]
+    show_attempts: Mutex<bool>,
+    /// 定期通知の間隔 (日数)。`None` のときは毎日
+    #[serde(default)]
+    interval: Mutex<Option<u32>>,
+    #[serde(default)]
+    color_bands: Mutex<difficulty::ColorBands>,
+    /// 問題の前に難易度の色の絵文字を付けるかどうか
+    #[serde(default)]
+    difficulty_emoji: Mutex<bool>,
+    /// 通知をテキストで転送するウェブフックのURL
+    #[serde(default)]
+    webhook: Mutex<Option<String>>,
+    /// 通知に含める提出の言語 (部分一致、大文字小文字を区別しない)。`None` のときはすべての言語
+    #[serde(default)]
+    language_filter: Mutex<Option<String>>,
+    /// 定期通知を一時停止しているかどうか。`/run` による手動の実行は停止中も行えます
+    #[serde(default)]
+    paused: Mutex<bool>,
+    /// 定期通知を一時停止した時刻。停止していないときや、記録する前に停止したときは `None`
+    #[serde(default)]
+    paused_since: Mutex<Option<DateTime<Utc>>>,
+    /// AtCoderのユーザー名ごとの、通知に表示する名前
+    #[serde(default)]
+    display_names: Mutex<BTreeMap<String, String>>,
+    /// 信頼度の低い難易度に印を付けるかどうか
+    #[serde(default)]
+    mark_low_confidence: Mutex<bool>,
+    /// 定期通知を行う時刻。`None` のときは環境変数 `DAILY_HOUR` の時刻
+    #[serde(default)]
+    report_time: Mutex<Option<NaiveTime>>,
+    /// コンテストの開始前にリマインドするかどうか
+    #[serde(default)]
+    reminder_enabled: Mutex<bool>,
+    /// コンテストの何分前にリマインドするか。`None` のときは [`contest_reminder::DEFAULT_REMINDER_MINUTES`]
+    #[serde(default)]
+    reminder_minutes: Mutex<Option<u32>>,
+}
+
+/// タイムゾーンが設定されていないときに使うタイムゾーン
+const DEFAULT_TIMEZONE: Tz = Tz::Asia__Tokyo;
+
+impl Data {
+    fn timezone(&self) -> Tz {
+        self.timezone.lock().unwrap().unwrap_or(DEFAULT_TIMEZONE)
+    }
+
+    /// 定期通知の間隔 (日数)
+    fn interval(&self) -> u32 {
+        self.interval.lock().unwrap().unwrap_or(1)
+    }
+
+    /// 通知に表示するユーザーの名前。設定されていなければAtCoderのユーザー名です。
+    fn display_name(&self, user: &str) -> String {
+        self.display_names
+            .lock()
+            .unwrap()
+            .get(user)
+            .cloned()
+            .unwrap_or(user.to_string())
+    }
+
+    /// 設定をすべて `other` の内容で置き換えます。
+    fn restore(&self, other: Data) {
+        *self.channel.lock().unwrap() = other.channel.into_inner().unwrap();
+        *self.guild.lock().unwrap() = other.guild.into_inner().unwrap();
+        *self.users.lock().unwrap() = other.users.into_inner().unwrap();
+        *self.first_ac_only.lock().unwrap() = other.first_ac_only.into_inner().unwrap();
+        *self.links.lock().unwrap() = other.links.into_inner().unwrap();
+        *self.dm.lock().unwrap() = other.dm.into_inner().unwrap();
+        *self.embed_color.lock().unwrap() = other.embed_color.into_inner().unwrap();
+        *self.role.lock().unwrap() = other.role.into_inner().unwrap();
+        *self.group_by_contest.lock().unwrap() = other.group_by_contest.into_inner().unwrap();
+        *self.goals.lock().unwrap() = other.goals.into_inner().unwrap();
+        *self.timezone.lock().unwrap() = other.timezone.into_inner().unwrap();
+        *self.post_format.lock().unwrap() = other.post_format.into_inner().unwrap();
+        *self.nudge_idle.lock().unwrap() = other.nudge_idle.into_inner().unwrap();
+        *self.show_attempts.lock().unwrap() = other.show_attempts.into_inner().unwrap();
+        *self.interval.lock().unwrap() = other.interval.into_inner().unwrap();
+        *self.color_bands.lock().unwrap() = other.color_bands.into_inner().unwrap();
+        *self.difficulty_emoji.lock().unwrap() = other.difficulty_emoji.into_inner().unwrap();
+        *self.webhook.lock().unwrap() = other.webhook.into_inner().unwrap();
+        *self.language_filter.lock().unwrap() = other.language_filter.into_inner().unwrap();
+        *self.paused.lock().unwrap() = other.paused.into_inner().unwrap();
+        *self.paused_since.lock().unwrap() = other.paused_since.into_inner().unwrap();
+        *self.display_names.lock().unwrap() = other.display_names.into_inner().unwrap();
+        *self.mark_low_confidence.lock().unwrap() = other.mark_low_confidence.into_inner().unwrap();
+        *self.report_time.lock().unwrap() = other.report_time.into_inner().unwrap();
+        *self.reminder_enabled.lock().unwrap() = other.reminder_enabled.into_inner().unwrap();
+        *self.reminder_minutes.lock().unwrap() = other.reminder_minutes.into_inner().unwrap();
+    }
+}
+
+/// 設定ファイルの形式のバージョン
+const CONFIG_VERSION: u64 = 1;
+
+/// 設定ファイルの中身。`Data` のフィールドに `version` を加えたものです。
+#[derive(Serialize)]
+struct Config<'a> {
+    version: u64,
+    #[serde(flatten)]
+    data: &'a Data,
+}
+
+impl<'a> Config<'a> {
+    fn new(data: &'a Data) -> Self {
+        Config {
+            version: CONFIG_VERSION,
+            data,
+        }
+    }
+}
+
+/// 設定ファイルを読み込みます。古い形式の場合は、現在の形式に移行してから読み込みます。
+fn parse_config(mut config: serde_json::Value) -> Result<Data, Error> {
+    // `version` のないファイルは、バージョン管理を始める前の形式 (v0)
+    let version = config
+        .get("version")
+        .map_or(Some(0), serde_json::Value::as_u64)
+        .context("Invalid config version")?;
+    if version > CONFIG_VERSION {
+        bail!("Unsupported config version: {}", version);
+    }
+    for version in version..CONFIG_VERSION {
+        migrate_config(&mut config, version);
+    }
+    Ok(serde_json::from_value(config)?)
+}
+
+/// 設定ファイルを `version` から次のバージョンの形式に移行します。
+fn migrate_config(config: &mut serde_json::Value, version: u64) {
+    match version {
+        // v0 → v1: フィールドはそのままで、`version` を付けるだけ
+        0 => config["version"] = 1.into(),
+        _ => unreachable!(),
+    }
+}
+
+/// 設定を保存するファイルのパス。環境変数 `CONFIG_PATH` で変更できます。
+fn config_path() -> String {
+    std::env::var("CONFIG_PATH").unwrap_or("config.json".into())
 }
 
 fn save(data: &Data) -> Result<(), Error> {
-    let data = serde_json::to_string(data)?;
-    std::fs::write("config.json", data)?;
+    let data = serde_json::to_string(&Config::new(data))?;
+    std::fs::write(config_path(), data)?;
     Ok(())
 }
 
+/// バックアップに含めない、秘密にすべき設定のキー
+const SENSITIVE_KEYS: &[&str] = &["webhook"];
+
+/// 全体の設定のバックアップを作ります。秘密にすべき設定は取り除きます。
+fn backup(data: &Data) -> Result<String, Error> {
+    let mut config = serde_json::to_value(Config::new(data))?;
+    if let Some(config) = config.as_object_mut() {
+        for key in SENSITIVE_KEYS {
+            config.remove(*key);
+        }
+    }
+    Ok(serde_json::to_string_pretty(&config)?)
+}
+
+/// バックアップを書き出すファイルのパス。設定ファイルのパスに時刻を付けたものです。
+fn backup_path() -> String {
+    format!(
+        "{}.{}.bak",
+        config_path(),
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+/// Botの管理者のユーザーID。環境変数 `OWNER_ID` で設定します。
+fn owner_id() -> Option<serenity::UserId> {
+    let id = std::env::var("OWNER_ID").ok()?;
+    match id.parse() {
+        Ok(id) => Some(serenity::UserId::new(id)),
+        Err(_) => {
+            println!("Invalid OWNER_ID: {}", id);
+            None
+        }
+    }
+}
+
 fn load() -> Result<Data, Error> {
-    let data = fs::read_to_string("config.json")?;
-    let data = serde_json::from_str(&data)?;
-    Ok(data)
+    let data = fs::read_to_string(config_path())?;
+    parse_config(serde_json::from_str(&data)?)
+}
+
+/// 新しく追加されたサーバーに送る、初期設定の案内
+const WELCOME_MESSAGE: &str = "AtCoder Botを追加していただきありがとうございます！\n\
+    1. 通知を送りたいチャンネルで /channel を実行してください。\n\
+    2. /register でAtCoderのユーザーを登録してください。\n\
+    毎日、登録されたユーザーが前日にACした問題を通知します。";
+
+/// 案内を投稿するチャンネルを選びます。
+/// システムチャンネルに投稿できればそこを、なければ投稿できる最初のテキストチャンネルを返します。
+fn welcome_channel(guild: &serenity::Guild, bot: serenity::UserId) -> Option<serenity::ChannelId> {
+    let member = guild.members.get(&bot)?;
+    let postable = |channel: &serenity::GuildChannel| {
+        let permissions = guild.user_permissions_in(channel, member);
+        channel.kind == serenity::ChannelType::Text
+            && permissions.view_channel()
+            && permissions.send_messages()
+    };
+    guild
+        .system_channel_id
+        .and_then(|id| guild.channels.get(&id))
+        .filter(|channel| postable(channel))
+        .or_else(|| {
+            guild
+                .channels
+                .values()
+                .filter(|channel| postable(channel))
+                .min_by_key(|channel| (channel.position, channel.id))
+        })
+        .map(|channel| channel.id)
 }
 
 async fn event_handler(
-    _ctx: &serenity::Context,
+    ctx: &serenity::Context,
     event: &serenity::FullEvent,
-    _framework: poise::FrameworkContext<'_, Data, Error>,
+    framework: poise::FrameworkContext<'_, Arc<Data>, Error>,
     data: &Data,
 ) -> Result<(), Error> {
-    if let serenity::FullEvent::Ready { data_about_bot } = event {
-        println!("Logged in as {}", data_about_bot.user.name);
-        match load() {
-            Ok(restore) => {
-                *data.channel.lock().unwrap() = *restore.channel.lock().unwrap();
-                *data.users.lock().unwrap() = restore.users.lock().unwrap().clone();
-                println!("Config restored:");
-                println!("{:#?}", data);
+    match event {
+        serenity::FullEvent::Ready { data_about_bot } => {
+            println!("Logged in as {}", data_about_bot.user.name);
+            match load() {
+                Ok(restore) => {
+                    data.restore(restore);
+                    println!("Config restored:");
+                    println!("{:#?}", data);
+                }
+                Err(_) => {
+                    println!("Note: {} not found, using default data", config_path());
+                }
             }
-            Err(_) => {
-                println!("Note: config.json not found, using default data");
+        }
+        serenity::FullEvent::GuildCreate {
+            guild,
+            is_new: Some(true),
+        } => match welcome_channel(guild, framework.bot_id) {
+            Some(channel) => {
+                channel
+                    .send_message(ctx, notify::new_message(None).content(WELCOME_MESSAGE))
+                    .await?;
+                println!("Welcome message sent: {:?} {:?}", guild.id, channel);
+            }
+            None => {
+                println!("No channel to send welcome message: {:?}", guild.id);
             }
+        },
+        serenity::FullEvent::GuildDelete { incomplete, .. }
+            if !incomplete.unavailable && *data.guild.lock().unwrap() == Some(incomplete.id) =>
+        {
+            data.restore(Data::default());
+            save(data)?;
+            println!("Removed from guild, config cleared: {:?}", incomplete.id);
         }
+        _ => {}
     }
     Ok(())
 }
 
+async fn on_error(error: poise::FrameworkError<'_, Arc<Data>, Error>) {
+    match error {
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            println!("Error in command `{}`: {:?}", ctx.command().name, error);
+            if let Err(e) = ctx.reply(error_message(&error)).await {
+                println!("Failed to send error message: {:?}", e);
+            }
+        }
+        poise::FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+            ..
+        } => {
+            let message = format!(
+                "このコマンドはあと {} 秒間実行できません。",
+                remaining_cooldown.as_secs() + 1
+            );
+            if let Err(e) = ctx.reply(message).await {
+                println!("Failed to send cooldown message: {:?}", e);
+            }
+        }
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                println!("Error while handling error: {:?}", e);
+            }
+        }
+    }
+}
+
+/// コマンドの実行中に起きたエラーを、ユーザー向けのメッセージにします。
+fn error_message(error: &Error) -> &'static str {
+    if error.is::<notify::ChannelNotSet>() {
+        "チャンネルが設定されていません。/channel で設定してください。"
+    } else if error.is::<api::RateLimited>() {
+        "AtCoder Problemsへのリクエストが制限されています。時間をおいて再度お試しください。"
+    } else if error.is::<serde_json::Error>() {
+        "AtCoder Problemsの応答を解析できませんでした。時間をおいて再度お試しください。"
+    } else if error.is::<reqwest::Error>() {
+        "AtCoder Problemsに接続できませんでした。時間をおいて再度お試しください。"
+    } else {
+        "エラーが発生しました。"
+    }
+}
+
+/// ボットのコマンド。Discordの制限で、1つのコマンドのサブコマンドは25個までです。
+fn commands() -> Vec<poise::Command<Arc<Data>, Error>> {
+    vec![
+        commands::group(
+            commands::config(),
+            vec![
+                commands::config_show(),
+                commands::channel(),
+                commands::group(
+                    commands::config_user(),
+                    vec![
+                        commands::register(),
+                        commands::unregister(),
+                        commands::registerlist(),
+                        commands::setusers(),
+                        commands::setname(),
+                        commands::link(),
+                        commands::unlink(),
+                        commands::with_fetch_cooldown(commands::inactive()),
+                        commands::with_fetch_cooldown(commands::removeinactive()),
+                    ],
+                ),
+                commands::firstaconly(),
+                commands::attempts(),
+                commands::langfilter(),
+                commands::dm(),
+                commands::embedcolor(),
+                commands::colorbands(),
+                commands::emoji(),
+                commands::confidence(),
+                commands::setrole(),
+                commands::groupbycontest(),
+                commands::timezone(),
+                commands::interval(),
+                commands::schedule(),
+                commands::reminder(),
+                commands::pause(),
+                commands::resume(),
+                commands::postformat(),
+                commands::nudge(),
+                commands::webhook(),
+            ],
+        ),
+        commands::group(
+            commands::admin(),
+            vec![
+                commands::export(),
+                commands::import(),
+                commands::backup(),
+                commands::reset(),
+                commands::with_fetch_cooldown(commands::refresh()),
+                commands::debuguser(),
+            ],
+        ),
+        commands::group(
+            commands::report(),
+            vec![
+                commands::with_fetch_cooldown(commands::run()),
+                commands::solvedtoday(),
+                commands::with_fetch_cooldown(commands::topdifficulty()),
+                commands::with_fetch_cooldown(commands::vcresult()),
+                commands::with_fetch_cooldown(commands::problemsolvers()),
+                commands::with_fetch_cooldown(commands::mostsolved()),
+                commands::with_fetch_cooldown(commands::ranking()),
+                commands::with_fetch_cooldown(commands::streakleaderboard()),
+                commands::contests(),
+                commands::difficulty(),
+                commands::problem(),
+                commands::with_fetch_cooldown(commands::random()),
+                commands::colors(),
+                commands::testpost(),
+            ],
+        ),
+        commands::group(
+            commands::user(),
+            vec![
+                commands::with_fetch_cooldown(commands::compare()),
+                commands::progress(),
+                commands::rating(),
+                commands::goal(),
+                commands::with_fetch_cooldown(commands::languagestats()),
+                commands::with_fetch_cooldown(commands::activity()),
+                commands::with_fetch_cooldown(commands::pending()),
+                commands::with_fetch_cooldown(commands::recommend()),
+                commands::with_fetch_cooldown(commands::stats()),
+            ],
+        ),
+        commands::about(),
+        commands::help(),
+        commands::status(),
+        // よく使うコマンドは、以前と同じくトップレベルでも使えるようにする
+        commands::channel(),
+        commands::register(),
+        commands::unregister(),
+        commands::registerlist(),
+        commands::link(),
+        commands::unlink(),
+        commands::with_fetch_cooldown(commands::run()),
+        commands::difficulty(),
+        commands::with_fetch_cooldown(commands::compare()),
+        commands::progress(),
+        commands::goal(),
+    ]
+}
+
 #[tokio::main]
 async fn main() {
-    dotenv().expect(".env file not found");
-
-    let token = std::env::var("DISCORD_TOKEN").expect("Missing DISCORD_TOKEN");
+    // .env、bot.tomlの順に読み込み、先に設定された値を優先します
+    if dotenv().is_err() {
+        println!("Note: .env file not found");
+    }
+    let token = match bot_config::apply().and_then(|()| bot_config::validate()) {
+        Ok(token) => token,
+        Err(e) => {
+            println!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
     let intents = serenity::GatewayIntents::non_privileged();
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                commands::channel(),
-                commands::register(),
-                commands::unregister(),
-                commands::registerlist(),
-                commands::run(),
-            ],
+            commands: commands(),
             event_handler: |ctx, event, framework, data| {
                 Box::pin(event_handler(ctx, event, framework, data))
             },
+            on_error: |error| Box::pin(on_error(error)),
+            // `owners_only` のコマンドは、OWNER_IDで指定したユーザーだけが使えるようにする
+            owners: owner_id().into_iter().collect(),
+            initialize_owners: false,
+            allowed_mentions: Some(notify::allowed_mentions(None)),
             ..Default::default()
         })
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                api::restore_problem_data();
                 tokio::spawn(daily_job::wait(ctx.clone()));
-                Ok(Data::default())
+                tokio::spawn(contest_reminder::wait(ctx.clone()));
+                let data = Arc::new(Data::default());
+                if let Ok(addr) = std::env::var("METRICS_ADDR") {
+                    tokio::spawn(metrics::serve(addr, ctx.clone(), data.clone()));
+                }
+                Ok(data)
             })
         })
         .build();
@@ -92,3 +538,70 @@ async fn main() {
         .await
         .expect("Failed to start client");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_config_is_migrated() {
+        let legacy = serde_json::json!({
+            "channel": "123",
+            "users": ["alice", "bob"],
+            "first_ac_only": true,
+        });
+        let data = parse_config(legacy).unwrap();
+        assert_eq!(
+            *data.channel.lock().unwrap(),
+            Some(serenity::ChannelId::new(123))
+        );
+        assert_eq!(
+            *data.users.lock().unwrap(),
+            BTreeSet::from(["alice".to_string(), "bob".to_string()])
+        );
+        assert!(*data.first_ac_only.lock().unwrap());
+        // 古い形式にないフィールドは既定値になります
+        assert!(data.links.lock().unwrap().is_empty());
+        assert_eq!(*data.role.lock().unwrap(), None);
+    }
+
+    #[test]
+    fn migration_adds_version() {
+        let mut config = serde_json::json!({ "users": [] });
+        migrate_config(&mut config, 0);
+        assert_eq!(config["version"], CONFIG_VERSION);
+    }
+
+    #[test]
+    fn saved_config_can_be_read_back() {
+        let data = Data::default();
+        data.users.lock().unwrap().insert("alice".into());
+        let config = serde_json::to_value(Config::new(&data)).unwrap();
+        assert_eq!(config["version"], CONFIG_VERSION);
+        let restored = parse_config(config).unwrap();
+        assert_eq!(*restored.users.lock().unwrap(), *data.users.lock().unwrap());
+    }
+
+    #[test]
+    fn newer_config_is_rejected() {
+        let config = serde_json::json!({ "version": CONFIG_VERSION + 1 });
+        assert!(parse_config(config).is_err());
+    }
+
+    #[test]
+    fn command_groups_fit_discord_limits() {
+        /// 1つのコマンドに指定できるサブコマンドの数の上限
+        const SUBCOMMANDS_LIMIT: usize = 25;
+        fn check(subcommands: &[poise::Command<Arc<Data>, Error>], depth: usize) {
+            assert!(subcommands.len() <= SUBCOMMANDS_LIMIT);
+            for command in subcommands {
+                // サブコマンドのグループの中に、さらにグループは作れません
+                assert!(depth < 2 || command.subcommands.is_empty());
+                check(&command.subcommands, depth + 1);
+            }
+        }
+        for command in commands() {
+            check(&command.subcommands, 1);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::daily_job;
+use poise::serenity_prelude as serenity;
+use serenity::Mentionable;
+
+/// 通知先のチャンネルを設定したときのメッセージ
+pub fn channel_set(channel: serenity::ChannelId, previous: Option<serenity::ChannelId>) -> String {
+    match previous {
+        Some(previous) if previous != channel => format!(
+            "以前のチャンネル {} から {} に変更しました。",
+            previous.mention(),
+            channel.mention()
+        ),
+        _ => format!("チャンネルを {} に設定しました。", channel.mention()),
+    }
+}
+
+/// ユーザーを登録したときのメッセージ。見つからなかったユーザーの行は含みません。
+pub fn registered(added: &[String], already: &[String]) -> Vec<String> {
+    let mut message = vec![format!(
+        "{}人追加、{}人は既に登録済みです。",
+        added.len(),
+        already.len()
+    )];
+    if !added.is_empty() {
+        message.push(format!("追加: {}", added.join(", ")));
+    }
+    if !already.is_empty() {
+        message.push(format!("登録済み: {}", already.join(", ")));
+    }
+    message
+}
+
+/// AtCoderにユーザーが見つからなかったときのメッセージ。近いユーザー名があれば提案します。
+pub fn user_not_found(user: &str, suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!("{} は見つかりません。もしかして: {}?", user, suggestion),
+        None => format!("ユーザー ({}) は見つかりませんでした。", user),
+    }
+}
+
+/// AtCoderへの問い合わせに失敗し、ユーザーを確認できなかったときのメッセージ
+pub fn user_lookup_failed(users: &[String]) -> String {
+    format!(
+        "ユーザー ({}) を確認できませんでした。時間をおいて再度お試しください。",
+        users.join(", ")
+    )
+}
+
+/// 登録されていないユーザーを指定されたときのメッセージ
+pub fn user_not_registered(user: &str) -> String {
+    format!("ユーザー ({}) は登録されていません。", user)
+}
+
+/// ユーザーの登録を解除したときのメッセージ
+pub fn unregistered(users: &[String]) -> String {
+    format!("ユーザー ({}) を登録解除しました。", users.join(", "))
+}
+
+/// 登録されているユーザーがいないときのメッセージ
+pub fn no_users() -> &'static str {
+    "まだユーザーが登録されていません。"
+}
+
+/// 登録されているユーザーの一覧の見出し
+pub fn registered_list_title(count: usize) -> String {
+    format!("登録されているユーザー ({}人)", count)
+}
+
+/// 夏時間の切り替えで、設定した時刻どおりに通知できない日についての注意
+pub fn time_conflict(conflict: &daily_job::TimeConflict) -> String {
+    match conflict {
+        daily_job::TimeConflict::Skipped(date, actual) => format!(
+            "注意: {} は夏時間の切り替えでこの時刻が存在しないため、{} に通知します。",
+            date.format("%Y-%m-%d"),
+            actual.format("%H:%M")
+        ),
+        daily_job::TimeConflict::Repeated(date) => format!(
+            "注意: {} は夏時間の切り替えでこの時刻が2回あるため、1回目に通知します。",
+            date.format("%Y-%m-%d")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_reports_duplicates_separately() {
+        assert_eq!(
+            registered(&["bob".into()], &["alice".into(), "carol".into()]),
+            [
+                "1人追加、2人は既に登録済みです。",
+                "追加: bob",
+                "登録済み: alice, carol",
+            ]
+        );
+    }
+
+    #[test]
+    fn registered_three_users() {
+        let added = ["alice", "bob", "carol"].map(String::from);
+        assert_eq!(
+            registered(&added, &[]),
+            [
+                "3人追加、0人は既に登録済みです。",
+                "追加: alice, bob, carol"
+            ]
+        );
+    }
+
+    #[test]
+    fn unregistered_lists_users() {
+        assert_eq!(
+            unregistered(&["alice".into(), "bob".into()]),
+            "ユーザー (alice, bob) を登録解除しました。"
+        );
+    }
+
+    #[test]
+    fn empty_user_list() {
+        assert_eq!(no_users(), "まだユーザーが登録されていません。");
+        assert_eq!(registered_list_title(0), "登録されているユーザー (0人)");
+    }
+
+    #[test]
+    fn channel_set_mentions_previous_channel() {
+        let channel = serenity::ChannelId::new(2);
+        assert_eq!(
+            channel_set(channel, None),
+            "チャンネルを <#2> に設定しました。"
+        );
+        assert_eq!(
+            channel_set(channel, Some(channel)),
+            "チャンネルを <#2> に設定しました。"
+        );
+        assert_eq!(
+            channel_set(channel, Some(serenity::ChannelId::new(1))),
+            "以前のチャンネル <#1> から <#2> に変更しました。"
+        );
+    }
+
+    #[test]
+    fn time_conflicts_explain_the_actual_time() {
+        use chrono::{NaiveDate, TimeZone};
+        let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let actual = chrono_tz::America::New_York
+            .with_ymd_and_hms(2024, 3, 10, 3, 0, 0)
+            .unwrap();
+        assert_eq!(
+            time_conflict(&daily_job::TimeConflict::Skipped(date, actual)),
+            "注意: 2024-03-10 は夏時間の切り替えでこの時刻が存在しないため、03:00 に通知します。"
+        );
+        assert_eq!(
+            time_conflict(&daily_job::TimeConflict::Repeated(date)),
+            "注意: 2024-03-10 は夏時間の切り替えでこの時刻が2回あるため、1回目に通知します。"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::Data;
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// AtCoder Problems などへのリクエスト数
+pub static API_REQUESTS: AtomicU64 = AtomicU64::new(0);
+/// 失敗したリクエスト数
+pub static API_ERRORS: AtomicU64 = AtomicU64::new(0);
+/// 最後に成功した通知にかかった時間 (ミリ秒)
+static LAST_RUN_DURATION_MS: AtomicU64 = AtomicU64::new(0);
+/// 最後に通知に成功した時刻 (UNIX時間)
+static LAST_SUCCESS: AtomicI64 = AtomicI64::new(0);
+
+/// 通知が成功したことを記録します。
+pub fn record_run(duration: Duration) {
+    LAST_RUN_DURATION_MS.store(duration.as_millis() as u64, Ordering::Relaxed);
+    LAST_SUCCESS.store(Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+fn render(ctx: &serenity::Context, data: &Data) -> String {
+    let users = data.users.lock().unwrap().len();
+    let mut body = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        writeln!(body, "# HELP {} {}", name, help).unwrap();
+        writeln!(body, "# TYPE {} {}", name, kind).unwrap();
+        writeln!(body, "{} {}", name, value).unwrap();
+    };
+    metric(
+        "atcoder_bot_guilds",
+        "gauge",
+        "Number of guilds the bot is in.",
+        ctx.cache.guild_count().to_string(),
+    );
+    metric(
+        "atcoder_bot_registered_users",
+        "gauge",
+        "Number of registered AtCoder users.",
+        users.to_string(),
+    );
+    metric(
+        "atcoder_bot_api_requests_total",
+        "counter",
+        "Number of requests sent to external APIs.",
+        API_REQUESTS.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "atcoder_bot_api_errors_total",
+        "counter",
+        "Number of failed requests to external APIs.",
+        API_ERRORS.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "atcoder_bot_last_run_duration_seconds",
+        "gauge",
+        "Duration of the last successful report run.",
+        (LAST_RUN_DURATION_MS.load(Ordering::Relaxed) as f64 / 1000.0).to_string(),
+    );
+    metric(
+        "atcoder_bot_last_success_timestamp_seconds",
+        "gauge",
+        "Unix time of the last successful report run.",
+        LAST_SUCCESS.load(Ordering::Relaxed).to_string(),
+    );
+    body
+}
+
+/// `addr` でPrometheus形式のメトリクスを公開します。
+/// 登録ユーザー数などは、ボットが使っている `data` から読み取ります。
+pub async fn serve(addr: String, ctx: serenity::Context, data: Arc<Data>) {
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("Failed to bind metrics endpoint to {}: {:?}", addr, e);
+            return;
+        }
+    };
+    println!("Serving metrics on {}", addr);
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let body = render(&ctx, &data);
+        tokio::spawn(async move {
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
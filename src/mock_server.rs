@@ -0,0 +1,172 @@
+//! テストで使う、AtCoder ProblemsのAPIの代わりになるHTTPサーバー
+//!
+//! テストではAPIのURLを作る前に必ずこのサーバーが起動し、`KENKOOOO_BASE_URL` がこのサーバーに向くため、
+//! 本物のAPIにリクエストが送られることはありません。
+//! サーバーはすべてのテストで1つを共有します。テストごとに別のユーザー名を使って、
+//! 応答が混ざらないようにしてください。
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// サーバーが返す応答
+#[derive(Clone)]
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Response {
+    pub fn json(body: serde_json::Value) -> Self {
+        Response {
+            status: 200,
+            headers: vec![("Content-Type".into(), "application/json".into())],
+            body: body.to_string(),
+        }
+    }
+
+    /// `Retry-After` に `seconds` を指定した 429 Too Many Requests
+    pub fn too_many_requests(seconds: u64) -> Self {
+        Response {
+            status: 429,
+            headers: vec![("Retry-After".into(), seconds.to_string())],
+            body: String::new(),
+        }
+    }
+
+    fn not_found() -> Self {
+        Response {
+            status: 404,
+            headers: vec![],
+            body: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// パスとクエリごとの、これから返す応答。最後の1つは何度でも返します
+    responses: HashMap<String, VecDeque<Response>>,
+    /// 受け取ったリクエストのパスとクエリ
+    requests: Vec<String>,
+}
+
+pub struct MockServer {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    /// `path` (クエリを含む) へのリクエストに、`responses` を順に返すようにします。
+    pub fn respond(&self, path: &str, responses: impl IntoIterator<Item = Response>) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .insert(path.into(), responses.into_iter().collect());
+    }
+
+    /// `prefix` で始まるパスへのリクエストの数を返します。
+    pub fn request_count(&self, prefix: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|path| path.starts_with(prefix))
+            .count()
+    }
+}
+
+fn respond(state: &Mutex<State>, path: &str) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push(path.into());
+    let Some(responses) = state.responses.get_mut(path) else {
+        return Response::not_found();
+    };
+    match responses.len() {
+        0 => Response::not_found(),
+        1 => responses[0].clone(),
+        _ => responses.pop_front().unwrap(),
+    }
+}
+
+/// 共有のサーバーを返します。初めて呼ばれたときに起動し、APIの向け先をこのサーバーにします。
+pub fn server() -> &'static MockServer {
+    static SERVER: OnceLock<MockServer> = OnceLock::new();
+    SERVER.get_or_init(|| {
+        let state = Arc::new(Mutex::new(State::default()));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        std::env::set_var(
+            "KENKOOOO_BASE_URL",
+            format!("http://{}", listener.local_addr().unwrap()),
+        );
+        // リクエストの間隔を空けると、テストが遅くなるだけなので
+        std::env::set_var("SUBMISSIONS_INTERVAL_MS", "0");
+        // 問題データのキャッシュなどを作業ディレクトリに書き出さないよう、設定ファイルを一時ディレクトリに置く
+        let dir = std::env::temp_dir().join(format!("atcoder-bot-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_var("CONFIG_PATH", dir.join("config.json"));
+
+        // テストごとのランタイムは終了するので、サーバーは専用のスレッドで動かす
+        let server_state = state.clone();
+        std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    serve(TcpListener::from_std(listener).unwrap(), server_state).await
+                })
+        });
+        MockServer { state }
+    })
+}
+
+/// `KENKOOOO_BASE_URL` に設定した、サーバーのURL
+pub fn url() -> String {
+    server();
+    std::env::var("KENKOOOO_BASE_URL").unwrap()
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<State>>) {
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+            let response = respond(&state, path);
+            let headers = response
+                .headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                .collect::<String>();
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.status,
+                headers,
+                response.body.len(),
+                response.body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
@@ -1,206 +1,2079 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    time::Instant,
+};
 
-use crate::{difficulty, load};
-use anyhow::{Context, Error};
-use chrono::{Duration, Local, NaiveTime};
-use poise::serenity_prelude as serenity;
-use reqwest::{
-    header::{HeaderMap, ACCEPT_ENCODING},
-    Client,
+use crate::{
+    api::{self, JudgeStatus},
+    bot_config::{self, LogLevel},
+    difficulty, load, metrics, Data,
 };
-use serde::Deserialize;
-use serenity::{CreateEmbed, CreateMessage};
-
-pub async fn notify(ctx: serenity::Context) -> Result<(), Error> {
-    #[allow(unused)]
-    #[derive(Clone, Deserialize, Debug, Default)]
-    struct ProblemModelItem {
-        slope: Option<f64>,
-        intercept: Option<f64>,
-        variance: Option<f64>,
-        difficulty: Option<i64>,
-        discrimination: Option<f64>,
-        irt_loglikelihood: Option<f64>,
-        irt_users: Option<i64>,
-        is_experimental: Option<bool>,
-    }
-
-    #[allow(unused)]
-    #[derive(Clone, Deserialize, Debug, Default)]
-    struct ProblemItem {
-        id: String,
-        contest_id: String,
-        problem_index: String,
-        name: String,
-        title: String,
-    }
-
-    #[derive(Clone, Deserialize, Debug, PartialEq)]
-    #[serde(rename_all = "UPPERCASE")]
-    enum JudgeStatus {
-        Ce,
-        Mle,
-        Tle,
-        Re,
-        Ole,
-        Ie,
-        Wa,
-        Ac,
-        Wj,
-        Wr,
-    }
-
-    #[allow(unused)]
-    #[derive(Clone, Deserialize, Debug)]
-    struct SubmissionItem {
-        id: i64,
-        epoch_second: i64,
-        problem_id: String,
-        contest_id: String,
-        user_id: String,
-        language: String,
-        point: f64,
-        length: i64,
-        result: JudgeStatus,
-        execution_time: Option<i64>,
-    }
-
-    struct ProblemDetail {
-        title: String,
-        difficulty: Option<i64>,
-        language: String,
-        submission_url: String,
-    }
-
-    impl ProblemDetail {
-        fn to_field(&self) -> (String, String, bool) {
+use anyhow::{Context, Error};
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use poise::{futures_util::future::join_all, serenity_prelude as serenity};
+use serde::{Deserialize, Serialize};
+use serenity::{CreateAllowedMentions, CreateEmbed, CreateMessage, Mentionable};
+
+struct ProblemDetail {
+    problem_id: String,
+    title: String,
+    contest_id: String,
+    contest_title: String,
+    problem_index: String,
+    difficulty: Option<i64>,
+    /// 難易度が難易度モデルにない推定値かどうか
+    difficulty_estimated: bool,
+    language: String,
+    /// コンテストの開催中に解いたかどうか。コンテストの時間が不明な場合は `None`
+    during_contest: Option<bool>,
+    problem_url: String,
+    submission_url: String,
+    /// 難易度の色の境界
+    color_bands: difficulty::ColorBands,
+    /// 難易度の推定の信頼度が低いかどうか
+    low_confidence: bool,
+    /// ACした時刻
+    epoch_second: i64,
+    submission_id: i64,
+    /// 登録されているユーザーの中で最初にACしたかどうか
+    first_blood: bool,
+}
+
+impl ProblemDetail {
+    /// 最初にACした場合に問題名の後に付ける印
+    fn first_blood_suffix(&self) -> &'static str {
+        if self.first_blood {
+            " 🥇 first"
+        } else {
+            ""
+        }
+    }
+
+    fn color(&self) -> difficulty::Color {
+        self.difficulty
+            .map(|d| self.color_bands.difficulty_color(d))
+            .unwrap_or(difficulty::Color::Black)
+    }
+
+    /// 正規化した難易度。推定値には `約` を付けます。
+    fn difficulty_label(&self) -> Option<(String, difficulty::Color)> {
+        self.difficulty.map(|d| {
+            let diff = difficulty::normalize(d);
+            let mut label = if self.difficulty_estimated {
+                format!("約{}", diff)
+            } else {
+                diff.to_string()
+            };
+            if self.low_confidence {
+                label.push('±');
+            }
+            (label, self.color_bands.color(diff))
+        })
+    }
+
+    fn summary(&self) -> String {
+        let summary = self.summary_with_links(&format!(
+            "[問題]({}) [提出]({})",
+            self.problem_url, self.submission_url
+        ));
+        if summary.chars().count() <= FIELD_VALUE_LIMIT {
+            summary
+        } else {
+            self.summary_with_links(&format!("[提出]({})", self.submission_url))
+        }
+    }
+
+    fn summary_with_links(&self, links: &str) -> String {
+        format!(
+            "{} | {} | {}{}",
+            self.difficulty_label()
+                .map(|(diff, color)| format!("{}({})", diff, color))
+                .unwrap_or("不明".into()),
+            self.language,
+            self.during_contest
+                .map(|during| if during { "本番 | " } else { "練習 | " })
+                .unwrap_or_default(),
+            links
+        )
+    }
+
+    /// `emoji` の場合に問題の前に付ける、難易度の色の絵文字
+    fn emoji_prefix(&self, emoji: bool) -> String {
+        if emoji {
+            format!("{} ", self.color().emoji())
+        } else {
+            String::new()
+        }
+    }
+
+    /// テキスト形式での1問分の表記 (`[ABC300 A](...) 緑(800)` など)
+    fn text_item(&self, emoji: bool) -> String {
+        format!(
+            "{}[{} {}]({}) {}{}",
+            self.emoji_prefix(emoji),
+            self.contest_id.to_uppercase(),
+            self.problem_index,
+            self.problem_url,
+            self.difficulty_label()
+                .map(|(diff, color)| format!("{}({})", color, diff))
+                .unwrap_or("不明".into()),
+            self.first_blood_suffix()
+        )
+    }
+
+    fn to_field(&self, emoji: bool) -> ReportField {
+        ReportField {
+            name: format!(
+                "{}{}{}",
+                self.emoji_prefix(emoji),
+                self.title,
+                self.first_blood_suffix()
+            ),
+            value: self.summary(),
+            color: self.color(),
+        }
+    }
+}
+
+/// 問題の情報を作るときの、サーバーごとの表示の設定
+#[derive(Clone, Copy)]
+struct DetailOptions {
+    color_bands: difficulty::ColorBands,
+    /// 信頼度の低い難易度に `±` を付けるかどうか
+    mark_low_confidence: bool,
+}
+
+impl DetailOptions {
+    fn new(data: &Data) -> Self {
+        DetailOptions {
+            color_bands: *data.color_bands.lock().unwrap(),
+            mark_low_confidence: *data.mark_low_confidence.lock().unwrap(),
+        }
+    }
+}
+
+/// 埋め込みのフィールド1つ分の内容
+struct ReportField {
+    name: String,
+    value: String,
+    color: difficulty::Color,
+}
+
+/// フィールドの値の最大文字数
+const FIELD_VALUE_LIMIT: usize = 1024;
+
+/// 埋め込み1つあたりのフィールド数の上限
+const EMBED_FIELDS_LIMIT: usize = 25;
+
+/// 1つのメッセージに含められる埋め込みの数の上限
+const MESSAGE_EMBEDS_LIMIT: usize = 10;
+
+/// 1つのメッセージに含まれる埋め込みの合計文字数の上限
+const EMBED_CHARS_LIMIT: usize = 6000;
+
+impl ReportField {
+    fn len(&self) -> usize {
+        self.name.chars().count() + self.value.chars().count()
+    }
+}
+
+/// フィールド数と文字数の上限を超えないように、フィールドを埋め込みごとに分けます。
+fn chunk_fields(fields: &[ReportField], title_len: usize) -> Vec<&[ReportField]> {
+    let mut chunks = vec![];
+    let mut start = 0;
+    let mut len = title_len;
+    for (i, field) in fields.iter().enumerate() {
+        if i - start == EMBED_FIELDS_LIMIT || len + field.len() > EMBED_CHARS_LIMIT {
+            chunks.push(&fields[start..i]);
+            start = i;
+            len = title_len;
+        }
+        len += field.len();
+    }
+    if start < fields.len() {
+        chunks.push(&fields[start..]);
+    }
+    chunks
+}
+
+/// 1つのメッセージの本文の最大文字数
+const MESSAGE_CHARS_LIMIT: usize = 2000;
+
+/// 解いた問題を、`**user**: 問題 · 問題 · ...` の形の行にします。
+/// 1行が長くなりすぎる場合は、複数の行に分けます。
+fn text_lines(header: &str, details: &[ProblemDetail], emoji: bool) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for item in details.iter().map(|p| p.text_item(emoji)) {
+        match lines.last_mut() {
+            Some(line)
+                if line.chars().count() + 3 + item.chars().count() <= MESSAGE_CHARS_LIMIT =>
+            {
+                line.push_str(" · ");
+                line.push_str(&item);
+            }
+            _ => lines.push(format!("{}: {}", header, item)),
+        }
+    }
+    lines
+}
+
+/// 文字数の上限を超えないように、行をメッセージごとにまとめます。
+pub fn chunk_lines(lines: &[String]) -> Vec<String> {
+    let mut chunks: Vec<String> = vec![];
+    for line in lines {
+        match chunks.last_mut() {
+            Some(chunk)
+                if chunk.chars().count() + 1 + line.chars().count() <= MESSAGE_CHARS_LIMIT =>
+            {
+                chunk.push('\n');
+                chunk.push_str(line);
+            }
+            _ => chunks.push(line.clone()),
+        }
+    }
+    chunks
+}
+
+/// 埋め込みに含まれる、文字数の上限の対象となる文字の数を数えます。
+fn embed_len(embed: &CreateEmbed) -> usize {
+    let embed = serde_json::to_value(embed).unwrap_or_default();
+    let len = |value: &serde_json::Value| value.as_str().map_or(0, |s| s.chars().count());
+    len(&embed["title"])
+        + len(&embed["description"])
+        + len(&embed["footer"]["text"])
+        + len(&embed["author"]["name"])
+        + embed["fields"].as_array().map_or(0, |fields| {
+            fields
+                .iter()
+                .map(|f| len(&f["name"]) + len(&f["value"]))
+                .sum()
+        })
+}
+
+/// 埋め込みの数と合計文字数の上限を超えないように、埋め込みをメッセージごとに分けます。
+fn chunk_embeds(embeds: &[CreateEmbed]) -> Vec<Vec<CreateEmbed>> {
+    let mut chunks: Vec<Vec<CreateEmbed>> = vec![];
+    let mut len = 0;
+    for embed in embeds {
+        let embed_len = embed_len(embed);
+        match chunks.last_mut() {
+            Some(chunk)
+                if chunk.len() < MESSAGE_EMBEDS_LIMIT && len + embed_len <= EMBED_CHARS_LIMIT =>
+            {
+                chunk.push(embed.clone());
+                len += embed_len;
+            }
+            _ => {
+                chunks.push(vec![embed.clone()]);
+                len = embed_len;
+            }
+        }
+    }
+    chunks
+}
+
+/// 問題をコンテストごとにまとめ、問題番号順に並べたフィールドを作ります。
+fn contest_fields(details: &[ProblemDetail], emoji: bool) -> Vec<ReportField> {
+    let mut fields = vec![];
+    let details = details
+        .iter()
+        .sorted_by_key(|p| {
             (
-                self.title.clone(),
-                format!(
-                    "{} | {} | [提出]({})",
-                    self.difficulty
-                        .map(|d| {
-                            let diff = difficulty::normalize(d);
-                            format!("{}({})", diff, difficulty::Color::from(diff))
-                        })
-                        .unwrap_or("不明".into()),
-                    self.language,
-                    self.submission_url
-                ),
-                false,
+                p.contest_id.clone(),
+                api::problem_index_key(&p.problem_index),
             )
+        })
+        .chunk_by(|p| &p.contest_id);
+    for (_, problems) in &details {
+        let mut field: Option<ReportField> = None;
+        for problem in problems {
+            let line = format!(
+                "{}{}{} | {}",
+                problem.emoji_prefix(emoji),
+                problem.title,
+                problem.first_blood_suffix(),
+                problem.summary()
+            );
+            match field.as_mut() {
+                Some(field)
+                    if field.value.chars().count() + 1 + line.chars().count()
+                        <= FIELD_VALUE_LIMIT =>
+                {
+                    field.value.push('\n');
+                    field.value.push_str(&line);
+                    field.color = field.color.max(problem.color());
+                }
+                _ => {
+                    fields.extend(field.take());
+                    field = Some(ReportField {
+                        name: problem.contest_title.clone(),
+                        value: line,
+                        color: problem.color(),
+                    });
+                }
+            }
         }
+        fields.extend(field);
     }
+    fields
+}
 
-    async fn http_get<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, Error> {
-        let client = Client::new();
-        let mut headers = HeaderMap::new();
-        headers.insert(ACCEPT_ENCODING, "gzip".parse().unwrap());
-        let res = client
-            .get(url)
-            .headers(headers)
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await?;
-        Ok(serde_json::from_str::<T>(&res)?)
+/// ユーザー1人分のフィールドを、上限を超えないように埋め込みに分けて並べます。
+/// `description` は最初の埋め込みにだけ付け、色は設定に応じて埋め込みごとに選びます。
+fn build_embed_for_user(
+    user: &str,
+    title: &str,
+    description: Option<String>,
+    fields: &[ReportField],
+    embed_color: difficulty::EmbedColor,
+) -> Vec<CreateEmbed> {
+    let header_len = title.chars().count() + description.as_ref().map_or(0, |d| d.chars().count());
+    chunk_fields(fields, header_len)
+        .into_iter()
+        .enumerate()
+        .map(|(i, fields)| {
+            let embed = match &description {
+                Some(description) if i == 0 => CreateEmbed::default().description(description),
+                _ => CreateEmbed::default(),
+            };
+            embed
+                .title(title)
+                .url(api::user_page_url(user))
+                .fields(
+                    fields
+                        .iter()
+                        .map(|f| (f.name.clone(), f.value.clone(), false)),
+                )
+                .color(match embed_color {
+                    difficulty::EmbedColor::Difficulty => {
+                        u32::from(fields.iter().map(|f| f.color).max().unwrap())
+                    }
+                    difficulty::EmbedColor::Fixed(color) => color,
+                })
+        })
+        .collect()
+}
+
+/// 週間の集計として、本番中とupsolveの内訳や先週比を表示する集計日数
+const WEEKLY_REPORT_DAYS: u32 = 7;
+
+/// 解いた問題を、本番中に解いたもの・upsolveしたもの・コンテストの時間が不明なものに分けて数えます。
+/// 同じ問題を本番中にも後からも解いた場合は、本番中に数えます。
+fn contest_breakdown(details: &[ProblemDetail]) -> String {
+    let mut during = HashSet::new();
+    let mut upsolve = HashSet::new();
+    let mut unknown = HashSet::new();
+    for p in details {
+        match p.during_contest {
+            Some(true) => during.insert(p.problem_id.as_str()),
+            Some(false) => upsolve.insert(p.problem_id.as_str()),
+            None => unknown.insert(p.problem_id.as_str()),
+        };
     }
+    let upsolve = upsolve.difference(&during).count();
+    let mut breakdown = format!("本番: {} 問 / upsolve: {} 問", during.len(), upsolve);
+    if !unknown.is_empty() {
+        breakdown.push_str(&format!(" / 不明: {} 問", unknown.len()));
+    }
+    breakdown
+}
 
-    let data = load()?;
+/// メンションしてよい相手を `role` だけに制限します。`@everyone` や `@here` は常に無効です。
+pub fn allowed_mentions(role: Option<serenity::RoleId>) -> CreateAllowedMentions {
+    CreateAllowedMentions::new()
+        .everyone(false)
+        .all_users(false)
+        .all_roles(false)
+        .roles(role)
+}
+
+/// メンションを `role` だけに制限したメッセージを作ります。
+pub fn new_message(role: Option<serenity::RoleId>) -> CreateMessage {
+    CreateMessage::default().allowed_mentions(allowed_mentions(role))
+}
+
+/// 先週と比べたAC数の増減 (`先週比 ↑ +15%` など)。先週のAC数が0の場合は `None` を返します。
+fn velocity(current: usize, previous: usize) -> Option<String> {
+    if previous == 0 {
+        return None;
+    }
+    let percent = (current as i64 - previous as i64) * 100 / previous as i64;
+    let arrow = match current.cmp(&previous) {
+        std::cmp::Ordering::Greater => "↑",
+        std::cmp::Ordering::Less => "↓",
+        std::cmp::Ordering::Equal => "→",
+    };
+    Some(format!("先週比 {} {:+}%", arrow, percent))
+}
+
+/// ACした問題の種類数を数えます。
+fn solved_count(details: &[ProblemDetail]) -> usize {
+    details.iter().map(|p| &p.problem_id).unique().count()
+}
+
+/// 通知先のチャンネルが設定されていないことを表すエラー
+#[derive(Debug)]
+pub struct ChannelNotSet;
+
+impl Display for ChannelNotSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Channel not set")
+    }
+}
+
+/// `tz` での今日の0時から `days_ago` 日前の0時を返します。
+pub fn start_of_day(tz: Tz, days_ago: i64) -> DateTime<Tz> {
+    let date = Utc::now().with_timezone(&tz).date_naive() - Duration::days(days_ago);
+    let midnight = date.and_time(NaiveTime::MIN);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight))
+}
+
+/// `/run` で遡ることのできる最大の日数
+pub const MAX_REPORT_DAYS: u32 = 7;
+
+/// 集計期間
+#[derive(Clone, Copy)]
+pub enum Period {
+    /// 今日の0時から指定した日数前の0時から、今日の0時まで
+    Days(u32),
+    /// 今日の0時から現在まで
+    Today,
+}
+
+impl Period {
+    /// 集計期間の始まりと終わりの時刻
+    fn range(self, tz: Tz) -> (i64, i64) {
+        match self {
+            Period::Days(days) => (
+                start_of_day(tz, days.into()).timestamp(),
+                start_of_day(tz, 0).timestamp(),
+            ),
+            Period::Today => (start_of_day(tz, 0).timestamp(), Utc::now().timestamp()),
+        }
+    }
+
+    /// 集計した日数
+    fn days(self) -> u32 {
+        match self {
+            Period::Days(days) => days,
+            Period::Today => 1,
+        }
+    }
+}
+
+impl Display for Period {
+    /// 集計期間の呼び方 (「昨日」「過去3日間」など)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Period::Days(1) => write!(f, "昨日"),
+            Period::Days(days) => write!(f, "過去{}日間", days),
+            Period::Today => write!(f, "今日"),
+        }
+    }
+}
+
+/// 通知の形式
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, poise::ChoiceParameter,
+)]
+pub enum PostFormat {
+    /// ユーザーごとの埋め込み
+    #[default]
+    #[name = "埋め込み"]
+    Embed,
+    /// Markdownの行
+    #[name = "テキスト"]
+    Text,
+}
+
+pub struct Report {
+    /// 集計期間
+    pub period: Period,
+    /// 集計したユーザーの数
+    pub user_count: usize,
+    /// ユーザーごとの埋め込み。テキスト形式の場合は空
+    pub user_embeds: Vec<(String, Vec<CreateEmbed>)>,
+    /// ユーザーごとのテキストの行。埋め込み形式の場合は空
+    pub user_lines: Vec<(String, Vec<String>)>,
+    /// 形式の設定によらない、全ユーザー分のテキストの行。ウェブフックへの転送に使います
+    pub plain_lines: Vec<String>,
+    pub total_solved: usize,
+    /// 前の同じ長さの期間の合計。週間の集計でない場合は `None`
+    pub previous_solved: Option<usize>,
+    pub max_difficulty: Option<u32>,
+    /// 難易度の色の境界
+    pub color_bands: difficulty::ColorBands,
+    /// 目標を設定しているユーザーの、今月の目標に対する進捗の行。週間の集計でない場合は空
+    pub goal_lines: Vec<String>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.user_embeds.is_empty() && self.user_lines.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.user_lines
+            .iter()
+            .flat_map(|(_, lines)| lines.clone())
+            .collect()
+    }
+
+    pub fn embeds(&self) -> Vec<CreateEmbed> {
+        self.user_embeds
+            .iter()
+            .flat_map(|(_, embeds)| embeds.clone())
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{}の合計: {} 問 (最高難易度: {})",
+            self.period,
+            self.total_solved,
+            self.max_difficulty
+                .map(|d| format!("{} {}", self.color_bands.color(d), d))
+                .unwrap_or("不明".into())
+        );
+        if let Some(velocity) = self
+            .previous_solved
+            .and_then(|previous| velocity(self.total_solved, previous))
+        {
+            summary.push_str(&format!(" {}", velocity));
+        }
+        for line in &self.goal_lines {
+            summary.push('\n');
+            summary.push_str(line);
+        }
+        summary
+    }
+
+    /// 誰もACしなかった場合のメッセージ
+    pub fn empty_message(&self) -> String {
+        if self.user_count == 0 {
+            "ユーザーが登録されていません。/register で登録してください。".into()
+        } else {
+            format!("{}は誰もACしませんでした。", self.period)
+        }
+    }
+}
+
+/// `from` から `to` までの間にユーザーがACした問題を取得します。
+/// `first_ac_only` の場合は、期間内に初めてACした問題だけを返します。
+async fn fetch_accepted(
+    api: &dyn api::AtcoderApi,
+    problem_data: &api::ProblemData,
+    user: &str,
+    from: i64,
+    to: i64,
+    first_ac_only: bool,
+    options: DetailOptions,
+) -> Result<Vec<ProblemDetail>, Error> {
+    let submissions =
+        api::fetch_submission_history(api, user, if first_ac_only { 0 } else { from }).await?;
+    Ok(accepted_details(
+        problem_data,
+        &submissions,
+        from,
+        to,
+        first_ac_only,
+        options,
+    ))
+}
+
+/// 提出のうち、`from` から `to` までの間にACした問題を取り出します。
+/// `first_ac_only` の場合は、期間内に初めてACした問題だけを返します。
+fn accepted_details(
+    problem_data: &api::ProblemData,
+    submissions: &[api::SubmissionItem],
+    from: i64,
+    to: i64,
+    first_ac_only: bool,
+    options: DetailOptions,
+) -> Vec<ProblemDetail> {
+    let mut accept_submissions = submissions
+        .iter()
+        .filter(|&s| (from..to).contains(&s.epoch_second))
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .collect::<Vec<_>>();
+
+    if first_ac_only {
+        let solved_before = submissions
+            .iter()
+            .filter(|s| s.epoch_second < from)
+            .filter(|s| s.result == JudgeStatus::Ac)
+            .map(|s| s.problem_id.as_str())
+            .collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        accept_submissions.retain(|s| {
+            !solved_before.contains(s.problem_id.as_str()) && seen.insert(s.problem_id.as_str())
+        });
+    }
+
+    accept_submissions
+        .into_iter()
+        .map(|submission| problem_detail(problem_data, submission, options))
+        .collect()
+}
+
+/// 挑戦として表示する、ACでない結果
+const ATTEMPT_STATUSES: [JudgeStatus; 5] = [
+    JudgeStatus::Wa,
+    JudgeStatus::Tle,
+    JudgeStatus::Re,
+    JudgeStatus::Mle,
+    JudgeStatus::Ole,
+];
+
+/// 提出のうち、`from` から `to` までの間に挑戦したもののACしなかった問題を取り出します。
+/// 同じ問題は1つにまとめ、期間内に後からACした問題は除きます。
+fn attempted_details(
+    problem_data: &api::ProblemData,
+    submissions: &[api::SubmissionItem],
+    from: i64,
+    to: i64,
+    options: DetailOptions,
+) -> Vec<ProblemDetail> {
+    let submissions = submissions
+        .iter()
+        .filter(|&s| (from..to).contains(&s.epoch_second))
+        .collect::<Vec<_>>();
+    let accepted = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .map(|s| s.problem_id.as_str())
+        .collect::<HashSet<_>>();
+    submissions
+        .into_iter()
+        .filter(|s| ATTEMPT_STATUSES.contains(&s.result))
+        .filter(|s| !accepted.contains(s.problem_id.as_str()))
+        .unique_by(|s| &s.problem_id)
+        .map(|submission| problem_detail(problem_data, submission, options))
+        .collect()
+}
+
+/// 挑戦した問題をまとめたフィールドを作ります。挑戦した問題がない場合は `None` を返します。
+fn attempts_field(details: &[ProblemDetail], emoji: bool) -> Option<ReportField> {
+    if details.is_empty() {
+        return None;
+    }
+    let mut lines: Vec<String> = vec![];
+    for (i, item) in details.iter().map(|p| p.text_item(emoji)).enumerate() {
+        // 収まらない分は、最後に残りの問題数だけを表示します
+        let rest = details.len() - i - 1;
+        let reserved = if rest > 0 {
+            format!("\nほか {} 問", rest).chars().count()
+        } else {
+            0
+        };
+        let len = lines.iter().map(|l| l.chars().count() + 1).sum::<usize>();
+        if len + item.chars().count() + reserved > FIELD_VALUE_LIMIT {
+            lines.push(format!("ほか {} 問", details.len() - i));
+            break;
+        }
+        lines.push(item);
+    }
+    Some(ReportField {
+        name: "挑戦 (未AC)".into(),
+        value: lines.join("\n"),
+        color: difficulty::Color::Black,
+    })
+}
+
+/// 提出した問題の情報をまとめます。
+fn problem_detail(
+    problem_data: &api::ProblemData,
+    submission: &api::SubmissionItem,
+    options: DetailOptions,
+) -> ProblemDetail {
+    let difficulty = problem_data.difficulty(&submission.problem_id);
+    let problem = problem_data
+        .problems
+        .get(&submission.problem_id)
+        .cloned()
+        .unwrap_or_else(|| api::ProblemItem::guess(&submission.problem_id));
+    let contest_id = if submission.contest_id.is_empty() {
+        &problem.contest_id
+    } else {
+        &submission.contest_id
+    };
+    ProblemDetail {
+        problem_id: submission.problem_id.clone(),
+        title: problem.title.clone(),
+        contest_id: contest_id.clone(),
+        contest_title: problem_data
+            .contests
+            .get(contest_id)
+            .map(|c| c.title.clone())
+            .unwrap_or(contest_id.clone()),
+        problem_index: problem.problem_index.clone(),
+        difficulty: difficulty.map(|(d, _)| d),
+        difficulty_estimated: difficulty.is_some_and(|(_, estimated)| estimated),
+        language: submission.language.clone(),
+        during_contest: problem_data
+            .contests
+            .get(contest_id)
+            .map(|c| c.is_during(submission.epoch_second)),
+        problem_url: format!(
+            "https://atcoder.jp/contests/{}/tasks/{}",
+            contest_id, submission.problem_id
+        ),
+        submission_url: format!(
+            "https://atcoder.jp/contests/{}/submissions/{}",
+            contest_id, submission.id
+        ),
+        color_bands: options.color_bands,
+        low_confidence: options.mark_low_confidence
+            && problem_data.is_low_confidence(&submission.problem_id),
+        epoch_second: submission.epoch_second,
+        submission_id: submission.id,
+        first_blood: false,
+    }
+}
+
+/// 直近 `days` 日間に登録されているユーザーがACした問題をまとめます。
+pub async fn build_report(
+    api: &dyn api::AtcoderApi,
+    data: &Data,
+    days: u32,
+) -> Result<Report, Error> {
     let users = data.users.lock().unwrap().clone();
-    let channel = (*data.channel.lock().unwrap()).context("Channel not set")?;
+    build_users_report(api, data, users, Period::Days(days)).await
+}
 
-    let problem_models: HashMap<String, ProblemModelItem> =
-        http_get("https://kenkoooo.com/atcoder/resources/problem-models.json").await?;
-    let problems: Vec<ProblemItem> =
-        http_get("https://kenkoooo.com/atcoder/resources/problems.json").await?;
+/// 通知を組み立てる前の、ユーザー1人分の集計結果
+struct UserReport {
+    user: String,
+    accept_details: Vec<ProblemDetail>,
+    attempt_details: Vec<ProblemDetail>,
+    /// 週間の集計での内訳と先週比
+    breakdown: Option<Vec<String>>,
+}
 
-    let mut embeds = vec![];
+/// 同じ問題を複数のユーザーがACした場合に、最初にACしたユーザーの問題に印を付けます。
+/// 同じ時刻の場合は、提出IDの小さい方を最初とします。
+fn mark_first_blood(user_reports: &mut [UserReport]) {
+    let mut firsts: HashMap<&str, ((i64, i64), usize, usize)> = HashMap::new();
+    let mut solvers: HashMap<&str, HashSet<usize>> = HashMap::new();
+    for (i, report) in user_reports.iter().enumerate() {
+        for (j, p) in report.accept_details.iter().enumerate() {
+            let key = (p.epoch_second, p.submission_id);
+            solvers.entry(&p.problem_id).or_default().insert(i);
+            firsts
+                .entry(&p.problem_id)
+                .and_modify(|first| {
+                    if key < first.0 {
+                        *first = (key, i, j);
+                    }
+                })
+                .or_insert((key, i, j));
+        }
+    }
+    let marks = firsts
+        .into_iter()
+        .filter(|(problem_id, _)| solvers[problem_id].len() >= 2)
+        .map(|(_, (_, i, j))| (i, j))
+        .collect::<Vec<_>>();
+    for (i, j) in marks {
+        user_reports[i].accept_details[j].first_blood = true;
+    }
+}
+
+/// 言語名に `filter` を含む提出だけを残します。大文字と小文字は区別しません。
+fn retain_language(submissions: &mut Vec<api::SubmissionItem>, filter: &str) {
+    let filter = filter.to_lowercase();
+    submissions.retain(|s| s.language.to_lowercase().contains(&filter));
+}
+
+/// `period` の間に `users` がACした問題をまとめます。
+pub async fn build_users_report(
+    api: &dyn api::AtcoderApi,
+    data: &Data,
+    users: impl IntoIterator<Item = String>,
+    period: Period,
+) -> Result<Report, Error> {
+    let days = period.days();
+    let weekly = days >= WEEKLY_REPORT_DAYS;
+    let first_ac_only = *data.first_ac_only.lock().unwrap();
+    let embed_color = *data.embed_color.lock().unwrap();
+    let group_by_contest = *data.group_by_contest.lock().unwrap();
+    let post_format = *data.post_format.lock().unwrap();
+    let show_attempts = *data.show_attempts.lock().unwrap();
+    let options = DetailOptions::new(data);
+    let emoji = *data.difficulty_emoji.lock().unwrap();
+    let links = data.links.lock().unwrap().clone();
+    let language_filter = data.language_filter.lock().unwrap().clone();
+    let goals = data.goals.lock().unwrap().clone();
+    let timezone = data.timezone();
+
+    let problem_data = api::problem_data(api).await?;
+
+    let mut user_reports = vec![];
+    let mut user_embeds = vec![];
+    let mut user_lines = vec![];
+    let mut plain_lines = vec![];
+    let mut total_solved = 0;
+    let mut previous_solved = weekly.then_some(0);
+    let mut max_difficulty = None;
+    let mut goal_lines = vec![];
+    let mut user_count = 0;
     for user in users {
-        println!("Processing user: {}", user);
+        if bot_config::log_level() >= LogLevel::Debug {
+            println!("Processing user: {}", user);
+        }
+        user_count += 1;
+
+        let (from, to) = period.range(timezone);
+        // 週間の集計では、先週比のために前の週の提出も取得します
+        let previous_from = Period::Days(days * 2).range(timezone).0;
+        let fetch_from = if first_ac_only {
+            0
+        } else if weekly {
+            previous_from
+        } else {
+            from
+        };
+
+        let mut submissions = match api::fetch_submission_history(api, &user, fetch_from).await {
+            Ok(submissions) => submissions,
+            Err(e) if e.is::<api::RateLimited>() => return Err(e),
+            Err(e) => {
+                println!("Skipping user {}: {:?}", user, e);
+                continue;
+            }
+        };
+        if let Some(filter) = &language_filter {
+            retain_language(&mut submissions, filter);
+        }
+        let accept_details = accepted_details(
+            &problem_data,
+            &submissions,
+            from,
+            to,
+            first_ac_only,
+            options,
+        );
+        let attempt_details = if show_attempts {
+            attempted_details(&problem_data, &submissions, from, to, options)
+        } else {
+            vec![]
+        };
+        let user_previous = weekly.then(|| {
+            solved_count(&accepted_details(
+                &problem_data,
+                &submissions,
+                previous_from,
+                from,
+                first_ac_only,
+                options,
+            ))
+        });
+
+        total_solved += solved_count(&accept_details);
+        if let (Some(total), Some(user_previous)) = (previous_solved.as_mut(), user_previous) {
+            *total += user_previous;
+        }
+        max_difficulty = max_difficulty.max(
+            accept_details
+                .iter()
+                .filter(|p| !p.difficulty_estimated)
+                .filter_map(|p| p.difficulty.map(difficulty::normalize))
+                .max(),
+        );
+
+        // 週間の集計では、目標を設定しているユーザーの今月の進捗も表示します
+        if let Some(&goal) = goals.get(&user).filter(|_| weekly) {
+            match count_monthly_solved(api, &user, timezone).await {
+                Ok(solved) => goal_lines.push(format!(
+                    "{} の今月の目標: {}",
+                    data.display_name(&user),
+                    goal_progress(solved, goal)
+                )),
+                Err(e) => println!("Failed to count monthly solved for {}: {:?}", user, e),
+            }
+        }
 
-        let from = (Local::now() - Duration::days(1))
-            .with_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-            .unwrap();
-        let to = Local::now()
-            .with_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-            .unwrap();
+        let breakdown = (weekly && !accept_details.is_empty()).then(|| {
+            let mut breakdown = vec![contest_breakdown(&accept_details)];
+            breakdown.extend(
+                user_previous
+                    .and_then(|previous| velocity(solved_count(&accept_details), previous)),
+            );
+            breakdown
+        });
 
-        let submissions_url = format!(
-            "https://kenkoooo.com/atcoder/atcoder-api/v3/user/submissions?user={}&from_second={}",
+        user_reports.push(UserReport {
             user,
-            from.timestamp()
+            accept_details,
+            attempt_details,
+            breakdown,
+        });
+    }
+
+    mark_first_blood(&mut user_reports);
+
+    for UserReport {
+        user,
+        accept_details,
+        attempt_details,
+        breakdown,
+    } in user_reports
+    {
+        let name = data.display_name(&user);
+        let header = match &breakdown {
+            Some(breakdown) => format!("**{}** ({})", name, breakdown.join(" / ")),
+            None => format!("**{}**", name),
+        };
+        let mut lines = text_lines(&header, &accept_details, emoji);
+        lines.extend(text_lines(
+            &format!("**{}** の挑戦 (未AC)", name),
+            &attempt_details,
+            emoji,
+        ));
+        plain_lines.extend(lines.iter().cloned());
+
+        if post_format == PostFormat::Text {
+            if !lines.is_empty() {
+                user_lines.push((user, lines));
+            }
+            continue;
+        }
+
+        let mut fields = if group_by_contest {
+            contest_fields(&accept_details, emoji)
+        } else {
+            accept_details
+                .iter()
+                .map(|p| p.to_field(emoji))
+                .collect::<Vec<_>>()
+        };
+        fields.extend(attempts_field(&attempt_details, emoji));
+
+        let title = format!(
+            "{} さんが{}ACした問題",
+            name,
+            match period {
+                Period::Days(1) | Period::Today => period.to_string(),
+                Period::Days(_) => format!("{}に", period),
+            }
+        );
+        // 紐付けられたDiscordアカウントがあれば、説明の先頭でメンションします
+        let description = links
+            .get(&user)
+            .map(|id| id.mention().to_string())
+            .into_iter()
+            .chain(breakdown.into_iter().flatten())
+            .collect::<Vec<_>>();
+        let embeds = build_embed_for_user(
+            &user,
+            &title,
+            (!description.is_empty()).then(|| description.join("\n")),
+            &fields,
+            embed_color,
         );
-        let submissions: Vec<SubmissionItem> = http_get(&submissions_url).await?;
 
-        let accept_submissions = submissions
+        if !embeds.is_empty() {
+            user_embeds.push((user, embeds));
+        }
+    }
+
+    Ok(Report {
+        period,
+        user_count,
+        user_embeds,
+        user_lines,
+        plain_lines,
+        total_solved,
+        previous_solved,
+        max_difficulty,
+        color_bands: options.color_bands,
+        goal_lines,
+    })
+}
+
+/// 難易度ランキングに表示する順位の数
+const TOP_DIFFICULTY_RANKS: usize = 10;
+
+/// 昨日登録されているユーザーがACした問題を、難易度の高い順に並べた埋め込みを作ります。
+/// 同じ難易度の問題は同じ順位にします。難易度の分かる問題を誰もACしていない場合は `None` を返します。
+pub async fn top_difficulty_embed(
+    api: &dyn api::AtcoderApi,
+    data: &Data,
+) -> Result<Option<CreateEmbed>, Error> {
+    let users = data.users.lock().unwrap().clone();
+    let (from, to) = Period::Days(1).range(data.timezone());
+    let options = DetailOptions::new(data);
+    let problem_data = api::problem_data(api).await?;
+
+    let accepted = join_all(
+        users
             .iter()
-            .filter(|&s| s.epoch_second < to.timestamp())
-            .filter(|s| s.result == JudgeStatus::Ac)
-            .collect::<Vec<_>>();
+            .map(|user| fetch_accepted(api, &problem_data, user, from, to, false, options)),
+    )
+    .await;
+    let mut solvers: HashMap<String, (ProblemDetail, BTreeSet<String>)> = HashMap::new();
+    for (user, details) in users.iter().zip(accepted) {
+        let details = match details {
+            Ok(details) => details,
+            Err(e) => {
+                println!("Skipping user {}: {:?}", user, e);
+                continue;
+            }
+        };
+        for detail in details.into_iter().filter(|p| p.difficulty.is_some()) {
+            solvers
+                .entry(detail.problem_id.clone())
+                .or_insert_with(|| (detail, BTreeSet::new()))
+                .1
+                .insert(user.clone());
+        }
+    }
+
+    let ranked = solvers
+        .into_values()
+        .sorted_by(|(a, _), (b, _)| {
+            b.difficulty
+                .cmp(&a.difficulty)
+                .then(a.problem_id.cmp(&b.problem_id))
+        })
+        .collect::<Vec<_>>();
+    let Some((top, _)) = ranked.first() else {
+        return Ok(None);
+    };
+    let mut lines = vec![];
+    let mut rank = 0;
+    for (i, (problem, users)) in ranked.iter().enumerate() {
+        if i == 0 || problem.difficulty != ranked[i - 1].0.difficulty {
+            rank = i + 1;
+        }
+        if rank > TOP_DIFFICULTY_RANKS {
+            break;
+        }
+        let Some((diff, color)) = problem.difficulty_label() else {
+            continue;
+        };
+        lines.push(format!(
+            "{}. [{}]({}) {}({}) — {}",
+            rank,
+            if problem.title.is_empty() {
+                &problem.problem_id
+            } else {
+                &problem.title
+            },
+            problem.problem_url,
+            color,
+            diff,
+            users.iter().map(|user| data.display_name(user)).join(", ")
+        ));
+    }
+    Ok(Some(
+        CreateEmbed::default()
+            .title("昨日ACされた問題の難易度ランキング")
+            .description(lines.join("\n"))
+            .color(u32::from(top.color())),
+    ))
+}
 
-        let accept_details = accept_submissions
+/// AC数ランキングの集計期間
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum RankingPeriod {
+    #[name = "昨日"]
+    Yesterday,
+    #[name = "過去7日間"]
+    Week,
+    #[name = "過去30日間"]
+    Month,
+}
+
+impl RankingPeriod {
+    fn days(self) -> u32 {
+        match self {
+            RankingPeriod::Yesterday => 1,
+            RankingPeriod::Week => 7,
+            RankingPeriod::Month => 30,
+        }
+    }
+}
+
+/// AC数ランキングで、順位の前に付けるメダル
+const RANKING_MEDALS: [&str; 3] = ["🥇", "🥈", "🥉"];
+
+/// 登録されているユーザーを、期間内にACした問題の数の多い順に並べた埋め込みを作ります。
+/// 同じ数の場合は、ACした問題の難易度の合計が大きい順にします。
+pub async fn ranking_embed(
+    api: &dyn api::AtcoderApi,
+    data: &Data,
+    period: RankingPeriod,
+) -> Result<CreateEmbed, Error> {
+    let users = data.users.lock().unwrap().clone();
+    let period = Period::Days(period.days());
+    let (from, to) = period.range(data.timezone());
+    let options = DetailOptions::new(data);
+    let problem_data = api::problem_data(api).await?;
+
+    let accepted = join_all(
+        users
             .iter()
-            .map(|submission| {
-                let problem_model = problem_models
-                    .get(&submission.problem_id)
-                    .cloned()
-                    .unwrap_or_default();
-                let problem = problems
+            .map(|user| fetch_accepted(api, &problem_data, user, from, to, false, options)),
+    )
+    .await;
+    let mut ranking = vec![];
+    for (user, details) in users.iter().zip(accepted) {
+        match details {
+            Ok(details) => {
+                let solved = details.iter().unique_by(|p| &p.problem_id).collect_vec();
+                let difficulty_sum = solved
                     .iter()
-                    .find(|p| p.id == submission.problem_id)
-                    .cloned()
-                    .unwrap_or_default();
-                ProblemDetail {
-                    title: problem.title.clone(),
-                    difficulty: problem_model.difficulty,
-                    language: submission.language.clone(),
-                    submission_url: format!(
-                        "https://atcoder.jp/contests/{}/submissions/{}",
-                        submission.contest_id, submission.id
-                    ),
+                    .filter_map(|p| p.difficulty.map(difficulty::normalize))
+                    .sum::<u32>();
+                ranking.push((user, solved.len(), difficulty_sum));
+            }
+            Err(e) if e.is::<api::RateLimited>() => return Err(e),
+            Err(e) => println!("Skipping user {}: {:?}", user, e),
+        }
+    }
+    ranking.sort_by(|(a_user, a, a_sum), (b_user, b, b_sum)| {
+        (b, b_sum).cmp(&(a, a_sum)).then(a_user.cmp(b_user))
+    });
+
+    let lines = ranking
+        .iter()
+        .map(|&(user, solved, difficulty_sum)| {
+            let rank = ranking
+                .iter()
+                .filter(|&&(_, s, sum)| (s, sum) > (solved, difficulty_sum))
+                .count()
+                + 1;
+            format!(
+                "{} {} — {} 問 (難易度の合計: {})",
+                RANKING_MEDALS
+                    .get(rank - 1)
+                    .map_or(format!("{}.", rank), |medal| medal.to_string()),
+                data.display_name(user),
+                solved,
+                difficulty_sum
+            )
+        })
+        .collect::<Vec<_>>();
+    Ok(CreateEmbed::default()
+        .title(format!("{}のAC数ランキング", period))
+        .description(if lines.is_empty() {
+            "ユーザーが登録されていません。".into()
+        } else {
+            lines.join("\n")
+        }))
+}
+
+/// おすすめの問題を選ぶときに、実力を推定する期間 (日数)
+const RECOMMEND_DAYS: u32 = 30;
+
+/// 最近ACした問題の難易度のうち、この割合 (%) の位置を実力とみなします
+const RECOMMEND_PERCENTILE: usize = 80;
+
+/// 最近ACした問題がないときに、実力とみなす難易度
+const RECOMMEND_BEGINNER_LEVEL: u32 = 0;
+
+/// おすすめの問題は、実力から実力にこの値を足した難易度までの範囲から選びます
+const RECOMMEND_RANGE: u32 = 300;
+
+/// おすすめする問題の数
+const RECOMMEND_PROBLEMS: usize = 5;
+
+/// ユーザーの最近のACから実力を推定し、少し難しいまだACしていない問題をおすすめする埋め込みを作ります。
+/// 実力は、直近 [`RECOMMEND_DAYS`] 日間にACした問題の難易度の [`RECOMMEND_PERCENTILE`] パーセンタイルです。
+pub async fn recommend_embed(
+    api: &dyn api::AtcoderApi,
+    data: &Data,
+    user: &str,
+) -> Result<CreateEmbed, Error> {
+    let options = DetailOptions::new(data);
+    let problem_data = api::problem_data(api).await?;
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+    // 今日のACも含めて数えます
+    let (from, _) = Period::Days(RECOMMEND_DAYS).range(data.timezone());
+    let now = Utc::now().timestamp();
+
+    let mut recent = accepted_details(&problem_data, &submissions, from, now, false, options)
+        .into_iter()
+        .unique_by(|p| p.problem_id.clone())
+        .filter(|p| !p.difficulty_estimated)
+        .filter_map(|p| p.difficulty.map(difficulty::normalize))
+        .collect::<Vec<_>>();
+    recent.sort();
+    let level = if recent.is_empty() {
+        RECOMMEND_BEGINNER_LEVEL
+    } else {
+        recent[(recent.len() - 1) * RECOMMEND_PERCENTILE / 100]
+    };
+
+    let solved = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .map(|s| s.problem_id.as_str())
+        .collect::<HashSet<_>>();
+    // 実力より少し上の、範囲の中央に近い問題から順に選びます
+    let target = level + RECOMMEND_RANGE / 2;
+    let recommended = problem_data
+        .problems
+        .values()
+        .filter(|p| !solved.contains(p.id.as_str()))
+        .filter_map(|p| {
+            let d = problem_data.problem_models.get(&p.id)?.difficulty?;
+            let normalized = difficulty::normalize(d);
+            (level < normalized && normalized <= level + RECOMMEND_RANGE).then_some((p, d))
+        })
+        .sorted_by_key(|&(p, d)| (difficulty::normalize(d).abs_diff(target), &p.id))
+        .take(RECOMMEND_PROBLEMS)
+        .collect::<Vec<_>>();
+
+    let lines = recommended
+        .iter()
+        .map(|&(p, d)| {
+            let color = options.color_bands.difficulty_color(d);
+            let experimental = problem_data
+                .problem_models
+                .get(&p.id)
+                .and_then(|m| m.is_experimental)
+                .unwrap_or(false);
+            format!(
+                "{} [{}](https://atcoder.jp/contests/{}/tasks/{}) {}({}){}",
+                color.emoji(),
+                p.title,
+                p.contest_id,
+                p.id,
+                color,
+                difficulty::normalize(d),
+                if experimental { " ※試験的" } else { "" }
+            )
+        })
+        .collect::<Vec<_>>();
+    let name = submissions.first().map_or(user, |s| s.user_id.as_str());
+    let basis = if recent.is_empty() {
+        format!(
+            "過去{}日間のACがないため、初心者向けの問題から選びました。",
+            RECOMMEND_DAYS
+        )
+    } else {
+        format!(
+            "過去{}日間のACから推定した実力: {} ({})",
+            RECOMMEND_DAYS,
+            level,
+            options.color_bands.color(level)
+        )
+    };
+    Ok(CreateEmbed::default()
+        .title(format!("{} さんへのおすすめの問題", name))
+        .url(api::user_page_url(user))
+        .description(if lines.is_empty() {
+            format!("{}\n条件に合う問題が見つかりませんでした。", basis)
+        } else {
+            format!("{}\n\n{}", basis, lines.join("\n"))
+        }))
+}
+
+/// 未ACの問題の一覧に表示する問題の最大数
+const PENDING_MAX_PROBLEMS: usize = 200;
+
+/// 未ACの問題の一覧で、1ページに表示する問題の数
+const PENDING_PAGE_SIZE: usize = 20;
+
+/// ユーザーが提出したもののまだACしていない問題を、難易度の低い順に並べた埋め込みを作ります。
+/// 難易度の分からない問題は最後に並べます。該当する問題がない場合は `None` を返します。
+pub async fn pending_embeds(
+    api: &dyn api::AtcoderApi,
+    data: &Data,
+    user: &str,
+) -> Result<Option<Vec<CreateEmbed>>, Error> {
+    let options = DetailOptions::new(data);
+    let emoji = *data.difficulty_emoji.lock().unwrap();
+    let problem_data = api::problem_data(api).await?;
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+    let pending = attempted_details(&problem_data, &submissions, 0, i64::MAX, options)
+        .into_iter()
+        .sorted_by(|a, b| {
+            (a.difficulty.is_none(), a.difficulty)
+                .cmp(&(b.difficulty.is_none(), b.difficulty))
+                .then(a.problem_id.cmp(&b.problem_id))
+        })
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return Ok(None);
+    }
+
+    let name = submissions.first().map_or(user, |s| s.user_id.as_str());
+    let title = if pending.len() > PENDING_MAX_PROBLEMS {
+        format!(
+            "{} さんの未ACの問題 ({}問中{}問)",
+            name,
+            pending.len(),
+            PENDING_MAX_PROBLEMS
+        )
+    } else {
+        format!("{} さんの未ACの問題 ({}問)", name, pending.len())
+    };
+    let lines = pending
+        .iter()
+        .take(PENDING_MAX_PROBLEMS)
+        .map(|p| p.text_item(emoji))
+        .collect::<Vec<_>>();
+    let pages = lines
+        .chunks(PENDING_PAGE_SIZE)
+        .map(|lines| {
+            CreateEmbed::default()
+                .title(&title)
+                .url(api::user_page_url(user))
+                .description(lines.join("\n"))
+        })
+        .collect();
+    Ok(Some(pages))
+}
+
+/// 誰もACしなかったときの呼びかけで、1行に並べるユーザーの数
+const NUDGE_NAMES_PER_LINE: usize = 10;
+
+pub async fn notify(ctx: serenity::Context, days: u32) -> Result<(), Error> {
+    let started = Instant::now();
+    let data = load()?;
+    let channel = (*data.channel.lock().unwrap()).context(ChannelNotSet)?;
+    let links = data.links.lock().unwrap().clone();
+    let dm = data.dm.lock().unwrap().clone();
+    let role = *data.role.lock().unwrap();
+
+    let report = build_report(&api::KenkooooApi, &data, days).await?;
+
+    // ウェブフックへの転送は、失敗してもDiscordへの投稿に影響しないよう別のタスクで行います
+    if let Some(webhook) = data.webhook.lock().unwrap().clone() {
+        let text = [
+            vec![if report.is_empty() {
+                report.empty_message()
+            } else {
+                report.summary()
+            }],
+            report.plain_lines.clone(),
+        ]
+        .concat()
+        .join("\n");
+        tokio::spawn(async move {
+            if let Err(e) = send_webhook(&webhook, &text).await {
+                println!("Failed to send report to webhook: {:?}", e);
+            }
+        });
+    }
+
+    for (user, embeds) in &report.user_embeds {
+        if let Some(&user_id) = links.get(user).filter(|id| dm.contains(id)) {
+            if let Err(e) = send_dm(&ctx, user_id, embeds).await {
+                println!("Failed to send DM to {}: {:?}", user_id, e);
+            }
+        }
+    }
+    for (user, lines) in &report.user_lines {
+        if let Some(&user_id) = links.get(user).filter(|id| dm.contains(id)) {
+            if let Err(e) = send_dm_lines(&ctx, user_id, lines).await {
+                println!("Failed to send DM to {}: {:?}", user_id, e);
+            }
+        }
+    }
+
+    let mut content = if report.is_empty() {
+        report.empty_message()
+    } else {
+        report.summary()
+    };
+    if let Some(role) = role {
+        content = format!("{} {}", role.mention(), content);
+    }
+    // ACしたユーザーに紐付けられたDiscordアカウントをメンションします
+    let mentioned = report
+        .user_embeds
+        .iter()
+        .map(|(user, _)| user)
+        .chain(report.user_lines.iter().map(|(user, _)| user))
+        .filter_map(|user| links.get(user).copied())
+        .unique()
+        .collect::<Vec<_>>();
+    if !mentioned.is_empty() {
+        content = format!(
+            "{} {}",
+            content,
+            mentioned.iter().map(|id| id.mention()).join(" ")
+        );
+    }
+
+    // 誰もACしなかった場合は、設定に応じて登録されているユーザーに呼びかけます
+    let mut nudge_lines = vec![];
+    let mut pinged = mentioned.clone();
+    if report.is_empty() && *data.nudge_idle.lock().unwrap() {
+        let users = data.users.lock().unwrap().clone();
+        let names = users
+            .iter()
+            .map(|user| match links.get(user) {
+                Some(&user_id) => {
+                    pinged.push(user_id);
+                    user_id.mention().to_string()
                 }
+                None => user.clone(),
             })
             .collect::<Vec<_>>();
-
-        embeds.extend(accept_details.chunks(25).map(|accepts| {
-            CreateEmbed::default()
-                .title(format!("{} さんが昨日ACした問題", user))
-                .url(format!("https://atcoder.jp/users/{}", user))
-                .fields(accepts.iter().map(|p| p.to_field()))
-                .color(u32::from(
-                    accepts
-                        .iter()
-                        .map(|p| {
-                            p.difficulty
-                                .map(difficulty::normalize)
-                                .map(difficulty::Color::from)
-                                .unwrap_or(difficulty::Color::Black)
-                        })
-                        .max()
-                        .unwrap(),
-                ))
-        }));
+        nudge_lines = names
+            .chunks(NUDGE_NAMES_PER_LINE)
+            .map(|names| names.join(" "))
+            .collect();
     }
 
-    if embeds.is_empty() {
+    if !report.user_lines.is_empty() || !nudge_lines.is_empty() {
+        let lines = [vec![content], report.lines(), nudge_lines].concat();
+        let mut messages = chunk_lines(&lines).into_iter();
         channel
             .send_message(
                 &ctx,
-                CreateMessage::default().content("昨日は誰もACしませんでした。"),
+                new_message(role)
+                    .content(messages.next().unwrap_or_default())
+                    .allowed_mentions(allowed_mentions(role).users(pinged.clone())),
             )
             .await?;
-    } else {
-        for embeds in embeds.chunks(10) {
+        for message in messages {
             channel
                 .send_message(
                     &ctx,
-                    CreateMessage::default().embeds(embeds.to_vec()),
+                    new_message(None)
+                        .content(message)
+                        .allowed_mentions(allowed_mentions(None).users(pinged.clone())),
                 )
                 .await?;
         }
+        metrics::record_run(started.elapsed());
+        return Ok(());
+    }
+
+    let mut embeds = chunk_embeds(&report.embeds()).into_iter();
+    channel
+        .send_message(
+            &ctx,
+            new_message(role)
+                .content(content)
+                .embeds(embeds.next().unwrap_or_default())
+                .allowed_mentions(allowed_mentions(role).users(mentioned)),
+        )
+        .await?;
+    for embeds in embeds {
+        channel
+            .send_message(&ctx, new_message(None).embeds(embeds))
+            .await?;
     }
 
+    metrics::record_run(started.elapsed());
+    Ok(())
+}
+
+/// ウェブフックに通知のテキストを送ります。
+/// Slackの `text` とDiscordなどの `content` の両方に同じ内容を入れたJSONを送ります。
+async fn send_webhook(url: &str, text: &str) -> Result<(), Error> {
+    api::client()
+        .post(url)
+        .json(&serde_json::json!({ "text": text, "content": text }))
+        .send()
+        .await?
+        .error_for_status()?;
     Ok(())
 }
+
+/// 投稿のテストに使う、架空のACの埋め込みを作ります。
+pub fn sample_embed() -> CreateEmbed {
+    let field = ProblemDetail {
+        problem_id: "practice_1".into(),
+        title: "A. Welcome to AtCoder".into(),
+        contest_id: "practice".into(),
+        contest_title: "practice contest".into(),
+        problem_index: "A".into(),
+        difficulty: Some(0),
+        difficulty_estimated: false,
+        language: "Rust (rustc 1.70.0)".into(),
+        during_contest: None,
+        problem_url: "https://atcoder.jp/contests/practice/tasks/practice_1".into(),
+        submission_url: "https://atcoder.jp/contests/practice/tasks/practice_1".into(),
+        color_bands: difficulty::ColorBands::default(),
+        low_confidence: false,
+        epoch_second: 0,
+        submission_id: 0,
+        first_blood: false,
+    }
+    .to_field(false);
+    CreateEmbed::default()
+        .title("テスト投稿")
+        .field(field.name, field.value, false)
+        .color(u32::from(field.color))
+}
+
+async fn send_dm(
+    ctx: &serenity::Context,
+    user_id: serenity::UserId,
+    embeds: &[CreateEmbed],
+) -> Result<(), Error> {
+    let channel = user_id.create_dm_channel(ctx).await?;
+    for embeds in chunk_embeds(embeds) {
+        channel
+            .send_message(ctx, new_message(None).embeds(embeds))
+            .await?;
+    }
+    Ok(())
+}
+
+async fn send_dm_lines(
+    ctx: &serenity::Context,
+    user_id: serenity::UserId,
+    lines: &[String],
+) -> Result<(), Error> {
+    let channel = user_id.create_dm_channel(ctx).await?;
+    for message in chunk_lines(lines) {
+        channel
+            .send_message(ctx, new_message(None).content(message))
+            .await?;
+    }
+    Ok(())
+}
+
+/// 目標に対する進捗 (`12/100 問 (12%)`)
+pub fn goal_progress(solved: usize, goal: u32) -> String {
+    format!(
+        "{}/{} 問 ({}%)",
+        solved,
+        goal,
+        solved * 100 / goal.max(1) as usize
+    )
+}
+
+/// `tz` での今月にACした問題の数を数えます。
+pub async fn count_monthly_solved(
+    api: &dyn api::AtcoderApi,
+    user: &str,
+    tz: Tz,
+) -> Result<usize, Error> {
+    let today = Utc::now().with_timezone(&tz).day();
+    let month_start = start_of_day(tz, (today - 1).into());
+    let submissions = api::fetch_submission_history(api, user, month_start.timestamp()).await?;
+    Ok(submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .map(|s| &s.problem_id)
+        .unique()
+        .count())
+}
+
+/// ユーザーが最後にACした時刻を返します。一度もACしていない場合は `None` を返します。
+pub async fn last_accepted(
+    api: &dyn api::AtcoderApi,
+    user: &str,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+    Ok(submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .map(|s| s.epoch_second)
+        .max()
+        .and_then(|t| DateTime::from_timestamp(t, 0)))
+}
+
+/// 最後のACから `days` 日以上経ったユーザーと、その最後のACの時刻を返します。
+/// 一度もACしていないユーザーを先に、それ以外は最後のACが古い順に並べます。
+pub async fn inactive_users(
+    api: &dyn api::AtcoderApi,
+    users: &BTreeSet<String>,
+    days: u32,
+) -> Vec<(String, Option<DateTime<Utc>>)> {
+    let last_accepted = join_all(users.iter().map(|user| last_accepted(api, user))).await;
+    let now = Utc::now();
+    let mut inactive = vec![];
+    for (user, last) in users.iter().zip(last_accepted) {
+        match last {
+            Ok(last) if last.is_none_or(|last| now - last >= Duration::days(days.into())) => {
+                inactive.push((user.clone(), last))
+            }
+            Ok(_) => {}
+            Err(e) => println!("Skipping user {}: {:?}", user, e),
+        }
+    }
+    inactive
+        .sort_by(|(a_user, a_last), (b_user, b_last)| a_last.cmp(b_last).then(a_user.cmp(b_user)));
+    inactive
+}
+
+/// ユーザーがACしたことのある問題のIDを返します。
+pub async fn solved_problems(
+    api: &dyn api::AtcoderApi,
+    user: &str,
+) -> Result<HashSet<String>, Error> {
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+    Ok(submissions
+        .into_iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .map(|s| s.problem_id)
+        .collect())
+}
+
+/// ユーザーが問題をACしたことがあるかどうかを返します。
+pub async fn has_solved(
+    api: &dyn api::AtcoderApi,
+    user: &str,
+    problem_id: &str,
+) -> Result<bool, Error> {
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+    Ok(submissions
+        .iter()
+        .any(|s| s.result == JudgeStatus::Ac && s.problem_id == problem_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, mock_server};
+
+    fn options() -> DetailOptions {
+        DetailOptions {
+            color_bands: difficulty::ColorBands::default(),
+            mark_low_confidence: false,
+        }
+    }
+
+    #[test]
+    fn solves_are_marked_by_contest_window() {
+        let problem_data = fixtures::problem_data(
+            vec![
+                fixtures::problem("abc300_a", "N-choice question"),
+                fixtures::problem("arc100_a", "Simple Problem"),
+            ],
+            &[],
+            vec![fixtures::contest("abc300", 1000, 6000)],
+        );
+        let detail = |problem_id: &str, epoch_second: i64| {
+            let submission =
+                fixtures::submission(1, "alice", problem_id, epoch_second, JudgeStatus::Ac);
+            problem_detail(&problem_data, &submission, options())
+        };
+
+        let during = detail("abc300_a", 2000);
+        assert_eq!(during.during_contest, Some(true));
+        assert!(during.summary().contains("本番 | "));
+
+        let after = detail("abc300_a", 7000);
+        assert_eq!(after.during_contest, Some(false));
+        assert!(after.summary().contains("練習 | "));
+
+        // コンテストの時間が分からない場合は、本番か練習かを表示しません
+        let unknown = detail("arc100_a", 2000);
+        assert_eq!(unknown.during_contest, None);
+        assert!(!unknown.summary().contains("本番"));
+        assert!(!unknown.summary().contains("練習"));
+    }
+
+    #[test]
+    fn long_fields_are_split_by_characters() {
+        let fields = (0..EMBED_FIELDS_LIMIT)
+            .map(|i| ReportField {
+                name: format!("問題 {}", i),
+                value: "x".repeat(FIELD_VALUE_LIMIT),
+                color: difficulty::Color::Gray,
+            })
+            .collect_vec();
+        let title_len = 20;
+        let chunks = chunk_fields(&fields, title_len);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), fields.len());
+        for chunk in chunks {
+            assert!(chunk.len() <= EMBED_FIELDS_LIMIT);
+            let len = title_len + chunk.iter().map(ReportField::len).sum::<usize>();
+            assert!(len <= EMBED_CHARS_LIMIT);
+        }
+    }
+
+    fn mock_api(submissions: Vec<api::SubmissionItem>) -> api::MockApi {
+        api::MockApi {
+            submissions,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn solved_problems_come_from_accepted_submissions() {
+        let api = mock_api(vec![
+            fixtures::submission(1, "alice", "abc300_a", 100, JudgeStatus::Wa),
+            fixtures::submission(2, "alice", "abc300_b", 200, JudgeStatus::Ac),
+            fixtures::submission(3, "alice", "abc300_b", 300, JudgeStatus::Ac),
+            fixtures::submission(4, "bob", "abc300_a", 400, JudgeStatus::Ac),
+        ]);
+        assert_eq!(
+            solved_problems(&api, "alice").await.unwrap(),
+            HashSet::from(["abc300_b".to_string()])
+        );
+        assert!(has_solved(&api, "alice", "abc300_b").await.unwrap());
+        assert!(!has_solved(&api, "alice", "abc300_a").await.unwrap());
+        assert_eq!(
+            last_accepted(&api, "alice").await.unwrap(),
+            DateTime::from_timestamp(300, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn monthly_solved_counts_distinct_problems_this_month() {
+        let now = Utc::now().timestamp();
+        let api = mock_api(vec![
+            fixtures::submission(1, "alice", "abc300_b", 0, JudgeStatus::Ac),
+            fixtures::submission(2, "alice", "abc300_a", now, JudgeStatus::Ac),
+            fixtures::submission(3, "alice", "abc300_a", now, JudgeStatus::Ac),
+            fixtures::submission(4, "alice", "abc300_c", now, JudgeStatus::Wa),
+        ]);
+        assert_eq!(
+            count_monthly_solved(&api, "alice", Tz::Asia__Tokyo)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn missing_model_is_shown_as_estimate_or_unknown() {
+        let problem_data = fixtures::problem_data(
+            vec![
+                fixtures::problem("abc300_d", "Estimated"),
+                fixtures::problem("abc301_d", "Known"),
+            ],
+            &[("abc301_d", 1200)],
+            vec![],
+        );
+        let detail = |problem_id: &str| {
+            let submission = fixtures::submission(1, "alice", problem_id, 0, JudgeStatus::Ac);
+            problem_detail(&problem_data, &submission, options())
+        };
+
+        let estimated = detail("abc300_d");
+        assert_eq!(estimated.difficulty, Some(1200));
+        assert!(estimated.difficulty_estimated);
+        assert!(estimated.summary().starts_with("約1200(水)"));
+
+        // 問題一覧にもない問題は、問題IDから推測して難易度を不明と表示します
+        let unknown = detail("abc999_z");
+        assert_eq!(unknown.difficulty, None);
+        assert_eq!(unknown.title, "abc999_z");
+        assert!(unknown.summary().starts_with("不明 | "));
+    }
+
+    #[test]
+    fn mentions_are_limited_to_the_role() {
+        let role = serenity::RoleId::new(42);
+        let mentions = serde_json::to_value(allowed_mentions(Some(role))).unwrap();
+        assert_eq!(
+            mentions,
+            serde_json::json!({ "parse": [], "roles": ["42"], "users": [] })
+        );
+        let message = serde_json::to_value(new_message(None)).unwrap();
+        assert_eq!(
+            message["allowed_mentions"],
+            serde_json::json!({ "parse": [], "roles": [], "users": [] })
+        );
+    }
+
+    fn field(name: &str, color: difficulty::Color) -> ReportField {
+        ReportField {
+            name: name.into(),
+            value: "不明 | Rust".into(),
+            color,
+        }
+    }
+
+    #[test]
+    fn all_unknown_embed_uses_fallback_color() {
+        let fields = [
+            field("A", difficulty::Color::Black),
+            field("B", difficulty::Color::Black),
+        ];
+        let embeds = build_embed_for_user(
+            "alice",
+            "alice",
+            None,
+            &fields,
+            difficulty::EmbedColor::Difficulty,
+        );
+        let embed = serde_json::to_value(&embeds[0]).unwrap();
+        assert_eq!(embed["color"], 0xcccccc);
+        assert_eq!(embed["fields"][0]["value"], "不明 | Rust");
+    }
+
+    #[test]
+    fn user_embeds_are_split_and_colored_per_chunk() {
+        let fields = (0..30)
+            .map(|i| match i {
+                3 => field("blue", difficulty::Color::Blue),
+                0..25 => field("green", difficulty::Color::Green),
+                _ => field("gray", difficulty::Color::Gray),
+            })
+            .collect_vec();
+        let embeds = build_embed_for_user(
+            "alice",
+            "alice (3 問)",
+            Some("本番: 1 問".into()),
+            &fields,
+            difficulty::EmbedColor::Difficulty,
+        )
+        .iter()
+        .map(|embed| serde_json::to_value(embed).unwrap())
+        .collect_vec();
+
+        assert_eq!(embeds.len(), 2);
+        for embed in &embeds {
+            assert_eq!(embed["title"], "alice (3 問)");
+            assert_eq!(embed["url"], "https://atcoder.jp/users/alice");
+        }
+        assert_eq!(embeds[0]["description"], "本番: 1 問");
+        assert!(embeds[1].get("description").is_none());
+        assert_eq!(embeds[0]["fields"].as_array().unwrap().len(), 25);
+        assert_eq!(embeds[1]["fields"].as_array().unwrap().len(), 5);
+        assert_eq!(embeds[0]["color"], 0x0000ff);
+        assert_eq!(embeds[1]["color"], 0x808080);
+
+        let fixed = build_embed_for_user(
+            "alice",
+            "alice",
+            None,
+            &fields[..1],
+            difficulty::EmbedColor::Fixed(0x123456),
+        );
+        assert_eq!(serde_json::to_value(&fixed[0]).unwrap()["color"], 0x123456);
+    }
+
+    #[test]
+    fn first_solver_gets_first_blood() {
+        let problem_data = fixtures::problem_data(
+            vec![
+                fixtures::problem("abc300_a", "A"),
+                fixtures::problem("abc300_b", "B"),
+            ],
+            &[],
+            vec![],
+        );
+        let report = |user: &str, solves: &[(i64, &str, i64)]| UserReport {
+            user: user.into(),
+            accept_details: solves
+                .iter()
+                .map(|&(id, problem_id, epoch_second)| {
+                    let submission =
+                        fixtures::submission(id, user, problem_id, epoch_second, JudgeStatus::Ac);
+                    problem_detail(&problem_data, &submission, options())
+                })
+                .collect(),
+            attempt_details: vec![],
+            breakdown: None,
+        };
+        let first_bloods = |reports: &[UserReport]| {
+            reports
+                .iter()
+                .map(|r| r.accept_details.iter().map(|p| p.first_blood).collect_vec())
+                .collect_vec()
+        };
+
+        let mut reports = [
+            report("alice", &[(3, "abc300_a", 200), (4, "abc300_b", 300)]),
+            report("bob", &[(1, "abc300_a", 100)]),
+        ];
+        mark_first_blood(&mut reports);
+        // 1人しか解いていない abc300_b には印を付けません
+        assert_eq!(first_bloods(&reports), [vec![false, false], vec![true]]);
+        assert!(reports[1].accept_details[0]
+            .first_blood_suffix()
+            .contains("🥇 first"));
+
+        // 同じ時刻なら、提出IDの小さい方が最初です
+        let mut reports = [
+            report("alice", &[(1, "abc300_a", 100)]),
+            report("bob", &[(2, "abc300_a", 100)]),
+        ];
+        mark_first_blood(&mut reports);
+        assert_eq!(first_bloods(&reports), [vec![true], vec![false]]);
+    }
+
+    #[test]
+    fn language_filter_keeps_matching_submissions() {
+        let mut submissions = [
+            "Rust (rustc 1.70.0)",
+            "C++ 20 (gcc 12.2)",
+            "rust",
+            "Python (PyPy 3.10)",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, language)| api::SubmissionItem {
+            language: language.into(),
+            ..fixtures::submission(i as i64, "alice", "abc300_a", 0, JudgeStatus::Ac)
+        })
+        .collect_vec();
+        retain_language(&mut submissions, "Rust");
+        assert_eq!(
+            submissions
+                .iter()
+                .map(|s| s.language.as_str())
+                .collect_vec(),
+            ["Rust (rustc 1.70.0)", "rust"]
+        );
+    }
+
+    #[test]
+    fn missing_problem_still_links_to_its_contest() {
+        let problem_data = fixtures::problem_data(vec![], &[], vec![]);
+        let submission = api::SubmissionItem {
+            contest_id: String::new(),
+            ..fixtures::submission(42, "alice", "abc400_a", 0, JudgeStatus::Ac)
+        };
+        let detail = problem_detail(&problem_data, &submission, options());
+        assert_eq!(detail.title, "abc400_a");
+        assert_eq!(detail.contest_id, "abc400");
+        assert_eq!(
+            detail.problem_url,
+            "https://atcoder.jp/contests/abc400/tasks/abc400_a"
+        );
+        assert_eq!(
+            detail.submission_url,
+            "https://atcoder.jp/contests/abc400/submissions/42"
+        );
+    }
+
+    #[tokio::test]
+    async fn report_is_built_from_api_responses() {
+        let server = mock_server::server();
+        server.respond(
+            "/resources/problems.json",
+            [mock_server::Response::json(serde_json::json!([
+                fixtures::problem("abc300_a", "N-choice question"),
+                fixtures::problem("abc300_b", "Same Map in the RPG World"),
+                fixtures::problem("abc300_c", "Cross"),
+            ]))],
+        );
+        server.respond(
+            "/resources/problem-models.json",
+            [mock_server::Response::json(serde_json::json!({
+                "abc300_a": { "difficulty": 500 },
+                "abc300_b": { "difficulty": 1600 },
+            }))],
+        );
+        server.respond(
+            "/resources/contests.json",
+            [mock_server::Response::json(serde_json::json!([
+                fixtures::contest("abc300", 0, 6000)
+            ]))],
+        );
+
+        let data = Data::default();
+        *data.first_ac_only.lock().unwrap() = true;
+        let (from, _) = Period::Days(1).range(data.timezone());
+        let submissions = |user: &str, submissions: &[(i64, &str, &str)]| {
+            mock_server::Response::json(
+                submissions
+                    .iter()
+                    .map(|&(id, problem_id, result)| {
+                        serde_json::json!({
+                            "id": id,
+                            "epoch_second": from + id,
+                            "problem_id": problem_id,
+                            "contest_id": "abc300",
+                            "user_id": user,
+                            "language": "Rust (rustc 1.70.0)",
+                            "point": 100.0,
+                            "length": 1000,
+                            "result": result,
+                            "execution_time": 1,
+                        })
+                    })
+                    .collect(),
+            )
+        };
+        // 初めてACした問題だけを数えるので、提出は最初から取得します
+        server.respond(
+            "/atcoder-api/v3/user/submissions?user=report_alice&from_second=0",
+            [submissions(
+                "report_alice",
+                &[
+                    (1, "abc300_a", "AC"),
+                    (2, "abc300_a", "AC"),
+                    (3, "abc300_b", "WA"),
+                    (4, "abc300_b", "AC"),
+                ],
+            )],
+        );
+        server.respond(
+            "/atcoder-api/v3/user/submissions?user=report_bob&from_second=0",
+            [submissions("report_bob", &[(5, "abc300_a", "AC")])],
+        );
+
+        let report = build_users_report(
+            &api::KenkooooApi,
+            &data,
+            ["report_alice".to_string(), "report_bob".to_string()],
+            Period::Days(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.user_count, 2);
+        assert_eq!(report.total_solved, 3);
+        assert_eq!(report.max_difficulty, Some(1600));
+
+        let embeds = report
+            .user_embeds
+            .iter()
+            .map(|(user, embeds)| {
+                assert_eq!(embeds.len(), 1);
+                (user.as_str(), serde_json::to_value(&embeds[0]).unwrap())
+            })
+            .collect::<Vec<_>>();
+        let (user, alice) = &embeds[0];
+        assert_eq!(*user, "report_alice");
+        assert_eq!(alice["title"], "report_alice さんが昨日ACした問題");
+        // 2回ACした abc300_a は1つにまとめ、埋め込みの色は最も難しい問題の色にします
+        let fields = alice["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0]["name"], "A. N-choice question 🥇 first");
+        assert!(fields[0]["value"].as_str().unwrap().starts_with("500(茶)"));
+        assert_eq!(fields[1]["name"], "B. Same Map in the RPG World");
+        assert!(fields[1]["value"].as_str().unwrap().starts_with("1600(青)"));
+        assert_eq!(alice["color"], u32::from(difficulty::Color::Blue));
+
+        let (user, bob) = &embeds[1];
+        assert_eq!(*user, "report_bob");
+        assert_eq!(bob["fields"].as_array().unwrap().len(), 1);
+        assert_eq!(bob["fields"][0]["name"], "A. N-choice question");
+        assert_eq!(bob["color"], u32::from(difficulty::Color::Brown));
+    }
+
+    #[test]
+    fn weekly_summary_shows_goal_progress() {
+        let report = Report {
+            period: Period::Days(7),
+            user_count: 2,
+            user_embeds: vec![],
+            user_lines: vec![],
+            plain_lines: vec![],
+            total_solved: 30,
+            previous_solved: Some(0),
+            max_difficulty: Some(1200),
+            color_bands: difficulty::ColorBands::default(),
+            goal_lines: vec![format!("alice の今月の目標: {}", goal_progress(12, 100))],
+        };
+        assert_eq!(
+            report.summary(),
+            "過去7日間の合計: 30 問 (最高難易度: 水 1200)\nalice の今月の目標: 12/100 問 (12%)"
+        );
+        // 目標を設定しているユーザーがいなければ、進捗の行は付けません
+        let report = Report {
+            goal_lines: vec![],
+            ..report
+        };
+        assert_eq!(
+            report.summary(),
+            "過去7日間の合計: 30 問 (最高難易度: 水 1200)"
+        );
+        assert_eq!(goal_progress(150, 100), "150/100 問 (150%)");
+    }
+}
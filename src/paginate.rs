@@ -0,0 +1,71 @@
+use crate::Context;
+use anyhow::Error;
+use poise::{serenity_prelude as serenity, CreateReply};
+use serenity::{
+    collector::ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+
+/// ボタン操作がこの時間なければページ送りを終了します。
+const PAGINATE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// 埋め込みを1ページずつ表示し、ボタンでページを切り替えられるようにします。
+pub async fn paginate(ctx: Context<'_>, pages: &[CreateEmbed]) -> Result<(), Error> {
+    let page = |i: usize| {
+        pages[i]
+            .clone()
+            .footer(CreateEmbedFooter::new(format!("{}/{}", i + 1, pages.len())))
+    };
+
+    if pages.len() == 1 {
+        ctx.send(CreateReply::default().embed(pages[0].clone()))
+            .await?;
+        return Ok(());
+    }
+
+    let ctx_id = ctx.id();
+    let prev_button_id = format!("{}prev", ctx_id);
+    let next_button_id = format!("{}next", ctx_id);
+    let components = vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(&prev_button_id).emoji('◀'),
+        CreateButton::new(&next_button_id).emoji('▶'),
+    ])];
+
+    let reply = ctx
+        .send(CreateReply::default().embed(page(0)).components(components))
+        .await?;
+
+    let mut current_page = 0;
+    while let Some(press) = ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(PAGINATE_TIMEOUT)
+        .await
+    {
+        if press.data.custom_id == next_button_id {
+            current_page = (current_page + 1) % pages.len();
+        } else if press.data.custom_id == prev_button_id {
+            current_page = current_page.checked_sub(1).unwrap_or(pages.len() - 1);
+        } else {
+            continue;
+        }
+
+        press
+            .create_response(
+                ctx.serenity_context(),
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new().embed(page(current_page)),
+                ),
+            )
+            .await?;
+    }
+
+    reply
+        .edit(
+            ctx,
+            CreateReply::default()
+                .embed(page(current_page))
+                .components(vec![]),
+        )
+        .await?;
+    Ok(())
+}
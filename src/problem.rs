@@ -0,0 +1,100 @@
+use crate::{api, difficulty};
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// 問題のタイトル・コンテスト・難易度の埋め込みを作ります。
+pub fn problem_embed(
+    problem_data: &api::ProblemData,
+    problem_id: &str,
+    color_bands: difficulty::ColorBands,
+) -> CreateEmbed {
+    let problem = problem_data
+        .problems
+        .get(problem_id)
+        .cloned()
+        .unwrap_or_else(|| api::ProblemItem::guess(problem_id));
+    let contest_title = problem_data
+        .contests
+        .get(&problem.contest_id)
+        .map_or(problem.contest_id.clone(), |c| c.title.clone());
+    let model = problem_data.problem_models.get(problem_id);
+
+    let embed = CreateEmbed::default()
+        .title(&problem.title)
+        .url(format!(
+            "https://atcoder.jp/contests/{}/tasks/{}",
+            problem.contest_id, problem_id
+        ))
+        .field(
+            "コンテスト",
+            format!(
+                "[{}](https://atcoder.jp/contests/{})",
+                contest_title, problem.contest_id
+            ),
+            false,
+        );
+    match problem_data.difficulty(problem_id) {
+        Some((d, estimated)) => {
+            let color = color_bands.difficulty_color(d);
+            let note = if estimated {
+                "同じシリーズの問題からの推定値"
+            } else if model.and_then(|m| m.is_experimental).unwrap_or(false) {
+                "試験的な推定値"
+            } else {
+                "AtCoder Problemsの推定値"
+            };
+            embed
+                .field(
+                    "難易度",
+                    format!("{} {} ({})", color.emoji(), difficulty::normalize(d), color),
+                    true,
+                )
+                .field("推定", note, true)
+                .color(u32::from(color))
+        }
+        None => embed.field("難易度", "不明", true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn problem_embed_shows_contest_and_difficulty() {
+        let problem_data = fixtures::problem_data(
+            vec![fixtures::problem("abc300_a", "N-choice question")],
+            &[("abc300_a", 1200)],
+            vec![fixtures::contest("abc300", 0, 6000)],
+        );
+        let embed = problem_embed(&problem_data, "abc300_a", difficulty::ColorBands::default());
+        let embed = serde_json::to_value(embed).unwrap();
+        assert_eq!(embed["title"], "A. N-choice question");
+        assert_eq!(
+            embed["url"],
+            "https://atcoder.jp/contests/abc300/tasks/abc300_a"
+        );
+        assert_eq!(
+            embed["fields"][0]["value"],
+            "[ABC300](https://atcoder.jp/contests/abc300)"
+        );
+        assert_eq!(embed["fields"][1]["name"], "難易度");
+        assert!(embed["fields"][1]["value"]
+            .as_str()
+            .unwrap()
+            .contains("1200"));
+    }
+
+    #[test]
+    fn problem_embed_without_difficulty_is_unknown() {
+        let problem_data = fixtures::problem_data(vec![], &[], vec![]);
+        let embed = problem_embed(&problem_data, "xyz001_a", difficulty::ColorBands::default());
+        let embed = serde_json::to_value(embed).unwrap();
+        assert_eq!(
+            embed["fields"][0]["value"],
+            "[xyz001](https://atcoder.jp/contests/xyz001)"
+        );
+        assert_eq!(embed["fields"][1]["value"], "不明");
+    }
+}
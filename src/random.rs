@@ -0,0 +1,208 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    api::{self, AtcoderApi},
+    difficulty, notify, problem,
+};
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use serenity::CreateEmbed;
+
+/// 取得したACした問題の一覧を、再取得せずに使う時間
+const SOLVED_CACHE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// ユーザーがACした問題のIDの一覧
+pub type SolvedProblems = Arc<HashSet<String>>;
+
+/// ユーザー名 (小文字) ごとの、ACした問題の一覧とその取得時刻
+static SOLVED_CACHE: Mutex<BTreeMap<String, (Instant, SolvedProblems)>> =
+    Mutex::new(BTreeMap::new());
+
+/// 問題を選ぶコンテストの種類
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum ContestKind {
+    #[name = "ABC"]
+    Abc,
+    #[name = "ARC"]
+    Arc,
+    #[name = "AGC"]
+    Agc,
+}
+
+impl ContestKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            ContestKind::Abc => "abc",
+            ContestKind::Arc => "arc",
+            ContestKind::Agc => "agc",
+        }
+    }
+}
+
+/// ユーザーがACしたことのある問題のIDを返します。最近取得したものがあればキャッシュを返します。
+pub async fn solved_problems(api: &dyn AtcoderApi, user: &str) -> Result<SolvedProblems, Error> {
+    let key = user.to_ascii_lowercase();
+    if let Some((fetched_at, solved)) = SOLVED_CACHE.lock().unwrap().get(&key) {
+        if fetched_at.elapsed() < SOLVED_CACHE_TTL {
+            return Ok(solved.clone());
+        }
+    }
+    let solved = Arc::new(notify::solved_problems(api, user).await?);
+    let mut cache = SOLVED_CACHE.lock().unwrap();
+    cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < SOLVED_CACHE_TTL);
+    cache.insert(key, (Instant::now(), solved.clone()));
+    Ok(solved)
+}
+
+/// 条件に合う問題をすべて返します。
+/// 難易度の範囲を指定した場合は、難易度の分かる問題だけを返します。
+async fn candidates<'a>(
+    api: &dyn AtcoderApi,
+    problem_data: &'a api::ProblemData,
+    min_difficulty: Option<u32>,
+    max_difficulty: Option<u32>,
+    kind: Option<ContestKind>,
+    unsolved_by: Option<&str>,
+) -> Result<Vec<&'a api::ProblemItem>, Error> {
+    let solved = match unsolved_by {
+        Some(user) => Some(solved_problems(api, user).await?),
+        None => None,
+    };
+    Ok(problem_data
+        .problems
+        .values()
+        .filter(|p| kind.is_none_or(|kind| p.contest_id.starts_with(kind.prefix())))
+        .filter(|p| solved.as_ref().is_none_or(|solved| !solved.contains(&p.id)))
+        .filter(|p| {
+            if min_difficulty.is_none() && max_difficulty.is_none() {
+                return true;
+            }
+            problem_data
+                .difficulty(&p.id)
+                .map(|(d, _)| difficulty::normalize(d))
+                .is_some_and(|d| {
+                    min_difficulty.is_none_or(|min| d >= min)
+                        && max_difficulty.is_none_or(|max| d <= max)
+                })
+        })
+        .collect())
+}
+
+/// 条件に合う問題を1つ選び、その埋め込みを作ります。
+/// 難易度の範囲を指定した場合は、難易度の分かる問題だけから選びます。
+/// 条件に合う問題がない場合は `None` を返します。
+pub async fn random_embed(
+    api: &dyn AtcoderApi,
+    min_difficulty: Option<u32>,
+    max_difficulty: Option<u32>,
+    kind: Option<ContestKind>,
+    unsolved_by: Option<&str>,
+    color_bands: difficulty::ColorBands,
+) -> Result<Option<CreateEmbed>, Error> {
+    let problem_data = api::problem_data(api).await?;
+    let candidates = candidates(
+        api,
+        &problem_data,
+        min_difficulty,
+        max_difficulty,
+        kind,
+        unsolved_by,
+    )
+    .await?;
+    Ok(candidates
+        .choose(&mut rand::thread_rng())
+        .map(|problem| problem::problem_embed(&problem_data, &problem.id, color_bands)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{JudgeStatus, MockApi},
+        fixtures,
+    };
+    use itertools::Itertools;
+
+    fn problem_data() -> api::ProblemData {
+        fixtures::problem_data(
+            ["abc300_a", "abc300_e", "arc150_a", "agc060_a", "abc301_g"]
+                .into_iter()
+                .map(|id| fixtures::problem(id, id))
+                .collect(),
+            &[
+                ("abc300_a", 50),
+                ("abc300_e", 1500),
+                ("arc150_a", 1200),
+                ("agc060_a", 2500),
+            ],
+            vec![],
+        )
+    }
+
+    async fn candidate_ids(
+        api: &MockApi,
+        min_difficulty: Option<u32>,
+        max_difficulty: Option<u32>,
+        kind: Option<ContestKind>,
+        unsolved_by: Option<&str>,
+    ) -> Vec<String> {
+        let problem_data = problem_data();
+        candidates(
+            api,
+            &problem_data,
+            min_difficulty,
+            max_difficulty,
+            kind,
+            unsolved_by,
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|p| p.id.clone())
+        .sorted()
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn candidates_match_all_filters() {
+        let api = MockApi {
+            submissions: vec![
+                fixtures::submission(1, "random_alice", "abc300_e", 0, JudgeStatus::Ac),
+                fixtures::submission(2, "random_alice", "arc150_a", 0, JudgeStatus::Wa),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            candidate_ids(&api, None, None, None, None).await,
+            ["abc300_a", "abc300_e", "abc301_g", "agc060_a", "arc150_a"]
+        );
+        // 難易度を指定すると、難易度の分からない abc301_g は選ばれません
+        assert_eq!(
+            candidate_ids(&api, Some(1200), Some(2000), None, None).await,
+            ["abc300_e", "arc150_a"]
+        );
+        assert_eq!(
+            candidate_ids(&api, None, Some(1000), None, None).await,
+            ["abc300_a"]
+        );
+        assert_eq!(
+            candidate_ids(&api, None, None, Some(ContestKind::Abc), None).await,
+            ["abc300_a", "abc300_e", "abc301_g"]
+        );
+        // ACした問題は除き、WAだけの問題は残します
+        assert_eq!(
+            candidate_ids(&api, Some(1200), None, None, Some("random_alice")).await,
+            ["agc060_a", "arc150_a"]
+        );
+        assert!(
+            candidate_ids(&api, Some(1400), None, Some(ContestKind::Arc), None)
+                .await
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{api, difficulty};
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// AtCoderにユーザーが存在しないことを表すエラー
+#[derive(Debug)]
+pub struct UserNotFound(pub String);
+
+impl Display for UserNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "User not found: {}", self.0)
+    }
+}
+
+/// 進捗バーの長さ
+const PROGRESS_BAR_LEN: u32 = 10;
+
+/// レーティングの履歴に表示する、直近のコンテストの数
+const RECENT_CONTESTS: usize = 3;
+
+/// 取得したレーティングを再取得せずに使う時間
+const RATING_CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// ユーザー名 (小文字) ごとの、レーティングとその取得時刻
+static RATING_CACHE: Mutex<BTreeMap<String, (Instant, Option<i64>)>> = Mutex::new(BTreeMap::new());
+
+/// ユーザーの現在のレーティングを返します。最近取得したものがあればキャッシュを返します。
+/// Ratedのコンテストに参加していない場合は `None` を、ユーザーが存在しない場合は [`UserNotFound`] を返します。
+pub async fn current_rating(user: &str) -> Result<Option<i64>, Error> {
+    let key = user.to_ascii_lowercase();
+    if let Some(&(fetched_at, rating)) = RATING_CACHE.lock().unwrap().get(&key) {
+        if fetched_at.elapsed() < RATING_CACHE_TTL {
+            return Ok(rating);
+        }
+    }
+    let rating = fetch_rating(user).await?;
+    let mut cache = RATING_CACHE.lock().unwrap();
+    cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < RATING_CACHE_TTL);
+    cache.insert(key, (Instant::now(), rating));
+    Ok(rating)
+}
+
+/// ユーザーのレーティングのキャッシュを破棄し、次回は再取得するようにします。
+pub fn invalidate_rating(user: &str) {
+    RATING_CACHE
+        .lock()
+        .unwrap()
+        .remove(&user.to_ascii_lowercase());
+}
+
+async fn fetch_rating(user: &str) -> Result<Option<i64>, Error> {
+    Ok(fetch_history(user)
+        .await?
+        .iter()
+        .rev()
+        .find(|r| r.is_rated)
+        .map(|r| r.new_rating))
+}
+
+/// ユーザーのコンテスト成績表を取得します。ユーザーが存在しない場合は [`UserNotFound`] を返します。
+async fn fetch_history(user: &str) -> Result<Vec<api::ContestResult>, Error> {
+    match api::fetch_contest_history(user).await {
+        Ok(history) => Ok(history),
+        Err(e)
+            if e.downcast_ref::<reqwest::Error>()
+                .and_then(|e| e.status())
+                .is_some_and(|s| s.is_client_error()) =>
+        {
+            Err(Error::msg(UserNotFound(user.to_string())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// `lower` から `next` までの色の範囲での進捗を、`■□□□` のような棒で表します。
+fn progress_bar(rating: u32, lower: u32, next: u32) -> String {
+    let filled = (rating - lower) * PROGRESS_BAR_LEN / (next - lower);
+    "■".repeat(filled as usize) + &"□".repeat((PROGRESS_BAR_LEN - filled) as usize)
+}
+
+/// レーティングと、次の色までの進捗の説明。色の境界はAtCoderの標準のものを使います。
+fn progress_description(rating: Option<i64>) -> String {
+    let Some(rating) = rating else {
+        return "まだRatedのコンテストに参加していません。".into();
+    };
+    let rating = rating.max(0) as u32;
+    let bands = difficulty::ColorBands::default();
+    let (color, lower, upper) = bands
+        .ranges()
+        .into_iter()
+        .rfind(|&(_, lower, _)| lower <= rating)
+        .unwrap();
+    match upper {
+        None => format!("{} ({})\n最高の色に到達しています！", rating, color),
+        Some(upper) => {
+            let next = upper + 1;
+            format!(
+                "{} ({})\n{} {}まであと {}",
+                rating,
+                color,
+                progress_bar(rating, lower, next),
+                bands.color(next),
+                next - rating
+            )
+        }
+    }
+}
+
+/// ユーザーのレーティングと、次の色までの進捗の埋め込みを作ります。
+pub async fn progress_embed(user: &str) -> Result<CreateEmbed, Error> {
+    let rating = current_rating(user).await?;
+    let embed = CreateEmbed::default()
+        .title(format!("{} さんのレーティング", user))
+        .url(api::user_page_url(user))
+        .description(progress_description(rating));
+    Ok(match rating {
+        Some(rating) => embed.color(u32::from(difficulty::Color::from(rating.max(0) as u32))),
+        None => embed,
+    })
+}
+
+/// ユーザーの現在と最高のレーティング、直近のコンテストの成績の埋め込みを作ります。
+pub async fn rating_embed(user: &str) -> Result<CreateEmbed, Error> {
+    let rated = fetch_history(user)
+        .await?
+        .into_iter()
+        .filter(|r| r.is_rated)
+        .collect::<Vec<_>>();
+    let embed = CreateEmbed::default()
+        .title(format!("{} さんのレーティング", user))
+        .url(api::user_page_url(user));
+    let (Some(current), Some(highest)) = (
+        rated.last().map(|r| r.new_rating),
+        rated.iter().map(|r| r.new_rating).max(),
+    ) else {
+        return Ok(embed.description("まだRatedのコンテストに参加していません。"));
+    };
+
+    let color = |rating: i64| difficulty::Color::from(rating.max(0) as u32);
+    let recent = rated
+        .iter()
+        .rev()
+        .take(RECENT_CONTESTS)
+        .map(|r| {
+            format!(
+                "{} — {}位 / パフォーマンス {} / {} ({:+})",
+                r.contest_name,
+                r.place,
+                r.performance,
+                r.new_rating,
+                r.new_rating - r.old_rating
+            )
+        })
+        .collect::<Vec<_>>();
+    Ok(embed
+        .description(format!(
+            "現在: {} ({})\n最高: {} ({})\nRated参加回数: {} 回",
+            current,
+            color(current),
+            highest,
+            color(highest),
+            rated.len()
+        ))
+        .field("直近のコンテスト", recent.join("\n"), false)
+        .color(u32::from(color(current))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_measured_within_the_color_band() {
+        assert_eq!(
+            progress_description(Some(0)),
+            "0 (灰)\n□□□□□□□□□□ 茶まであと 400"
+        );
+        assert_eq!(
+            progress_description(Some(399)),
+            "399 (灰)\n■■■■■■■■■□ 茶まであと 1"
+        );
+        assert_eq!(
+            progress_description(Some(400)),
+            "400 (茶)\n□□□□□□□□□□ 緑まであと 400"
+        );
+        assert_eq!(
+            progress_description(Some(2799)),
+            "2799 (橙)\n■■■■■■■■■□ 赤まであと 1"
+        );
+        assert_eq!(
+            progress_description(Some(2800)),
+            "2800 (赤)\n最高の色に到達しています！"
+        );
+        assert_eq!(
+            progress_description(None),
+            "まだRatedのコンテストに参加していません。"
+        );
+    }
+}
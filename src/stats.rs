@@ -0,0 +1,83 @@
+use crate::{
+    api::{self, AtcoderApi, JudgeStatus},
+    difficulty, rating, streak,
+};
+use anyhow::Error;
+use chrono_tz::Tz;
+use itertools::Itertools;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// AtCoder Problemsでのユーザーの統計の埋め込みを作ります。
+/// ユーザーが存在しない場合は [`rating::UserNotFound`] を返します。
+pub async fn stats_embed(
+    api: &dyn AtcoderApi,
+    user: &str,
+    tz: Tz,
+    color_bands: difficulty::ColorBands,
+) -> Result<CreateEmbed, Error> {
+    let (ac, point_sum, longest_streak) = match tokio::try_join!(
+        api::fetch_user_rank("ac_rank", user),
+        api::fetch_user_rank("rated_point_sum_rank", user),
+        api::fetch_user_rank("streak_rank", user),
+    ) {
+        Ok(ranks) => ranks,
+        Err(e)
+            if e.downcast_ref::<reqwest::Error>()
+                .and_then(|e| e.status())
+                .is_some_and(|s| s.is_client_error()) =>
+        {
+            return Err(Error::msg(rating::UserNotFound(user.to_string())));
+        }
+        Err(e) => return Err(e),
+    };
+    let problem_data = api::problem_data(api).await?;
+    let submissions = api::fetch_submission_history(api, user, 0).await?;
+
+    // 難易度の推定値ではなく、AtCoder Problemsの難易度が分かる問題の中で最も難しいもの
+    let hardest = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .unique_by(|s| &s.problem_id)
+        .filter_map(|s| {
+            problem_data
+                .difficulty(&s.problem_id)
+                .filter(|&(_, estimated)| !estimated)
+                .map(|(d, _)| (d, &s.problem_id))
+        })
+        .max();
+
+    let mut lines = vec![
+        format!("AC数: {} 問 ({}位)", ac.count, ac.rank),
+        format!(
+            "Rated Point Sum: {} ({}位)",
+            point_sum.count, point_sum.rank
+        ),
+        format!(
+            "連続AC: 現在 {} 日 / 最長 {} 日",
+            streak::current_streak(&submissions, tz),
+            longest_streak.count
+        ),
+    ];
+    lines.push(match hardest {
+        Some((d, problem_id)) => format!(
+            "最高難易度: {} {} ({})",
+            color_bands.difficulty_color(d),
+            difficulty::normalize(d),
+            problem_id
+        ),
+        None => "最高難易度: 不明".into(),
+    });
+
+    let embed = CreateEmbed::default()
+        .title(format!(
+            "{} さんの統計",
+            submissions.first().map_or(user, |s| s.user_id.as_str())
+        ))
+        .url(api::user_page_url(user))
+        .description(lines.join("\n"));
+    Ok(match hardest {
+        Some((d, _)) => embed.color(u32::from(color_bands.difficulty_color(d))),
+        None => embed,
+    })
+}
@@ -0,0 +1,76 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::api::{self, AtcoderApi, JudgeStatus};
+use anyhow::Error;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use poise::{futures_util::future::join_all, serenity_prelude as serenity};
+use serenity::CreateEmbed;
+
+/// ランキングに表示するユーザーの数
+const LEADERBOARD_USERS: usize = 20;
+
+/// 今日まで (今日まだACしていなければ昨日まで) 毎日ACを続けている日数を数えます。
+pub fn current_streak(submissions: &[api::SubmissionItem], tz: Tz) -> u32 {
+    let days = submissions
+        .iter()
+        .filter(|s| s.result == JudgeStatus::Ac)
+        .filter_map(|s| DateTime::from_timestamp(s.epoch_second, 0))
+        .map(|t| t.with_timezone(&tz).date_naive())
+        .collect::<HashSet<NaiveDate>>();
+    let today = Utc::now().with_timezone(&tz).date_naive();
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+/// 登録されているユーザーを、連続でACしている日数の長い順に並べた埋め込みを作ります。
+pub async fn streak_leaderboard_embed(
+    api: &dyn AtcoderApi,
+    users: &BTreeSet<String>,
+    tz: Tz,
+) -> Result<CreateEmbed, Error> {
+    let histories = join_all(
+        users
+            .iter()
+            .map(|user| api::fetch_submission_history(api, user, 0)),
+    )
+    .await;
+    let mut streaks = vec![];
+    for (user, history) in users.iter().zip(histories) {
+        match history {
+            Ok(history) => streaks.push((user, current_streak(&history, tz))),
+            Err(e) if e.is::<api::RateLimited>() => return Err(e),
+            Err(e) => println!("Skipping user {}: {:?}", user, e),
+        }
+    }
+    streaks.sort_by(|(a_user, a), (b_user, b)| b.cmp(a).then(a_user.cmp(b_user)));
+
+    let lines = streaks
+        .iter()
+        .take(LEADERBOARD_USERS)
+        .map(|&(user, streak)| {
+            let rank = streaks.iter().filter(|&&(_, s)| s > streak).count() + 1;
+            if streak == 0 {
+                format!("{}. {} — なし", rank, user)
+            } else {
+                format!("{}. {} — 🔥 {} 日", rank, user, streak)
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(CreateEmbed::default()
+        .title("連続AC日数ランキング")
+        .description(if lines.is_empty() {
+            "ユーザーが登録されていません。".into()
+        } else {
+            lines.join("\n")
+        }))
+}
@@ -0,0 +1,109 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::api;
+use anyhow::Error;
+use itertools::Itertools;
+
+/// 候補として取得するAC数ランキングの人数
+const RANKING_USERS: usize = 1000;
+
+/// 取得したランキングを再取得せずに使う時間
+const RANKING_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 候補として提案する、編集距離の最大値
+const MAX_DISTANCE: usize = 2;
+
+/// AC数ランキングのユーザー名とその取得時刻
+static RANKING_CACHE: Mutex<Option<(Instant, Vec<String>)>> = Mutex::new(None);
+
+/// 候補にするユーザー名の一覧を返します。最近取得したものがあればキャッシュを返します。
+async fn ranking_users() -> Result<Vec<String>, Error> {
+    if let Some((fetched_at, users)) = RANKING_CACHE.lock().unwrap().as_ref() {
+        if fetched_at.elapsed() < RANKING_CACHE_TTL {
+            return Ok(users.clone());
+        }
+    }
+    let users = api::fetch_ac_ranking(0, RANKING_USERS)
+        .await?
+        .into_iter()
+        .map(|r| r.user_id)
+        .collect::<Vec<_>>();
+    RANKING_CACHE
+        .lock()
+        .unwrap()
+        .replace((Instant::now(), users.clone()));
+    Ok(users)
+}
+
+/// 大文字と小文字を区別しない編集距離
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.to_ascii_lowercase().chars().collect::<Vec<_>>();
+    let b = b.to_ascii_lowercase().chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (prev + usize::from(ca != cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// `candidates` の中から、`user` との編集距離が [`MAX_DISTANCE`] 以下で最も近いものを返します。
+/// 同じ距離のものがある場合は、先に現れたものを返します。
+fn closest<'a>(user: &str, candidates: impl IntoIterator<Item = &'a String>) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(user, candidate), candidate))
+        .filter(|&(d, _)| d <= MAX_DISTANCE)
+        .min_set_by_key(|&(d, _)| d)
+        .into_iter()
+        .next()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// 見つからなかったユーザー名に近いユーザー名を、`known` とAC数ランキングから探します。
+/// ランキングを取得できなかった場合は `known` だけから探し、近いものがない場合は `None` を返します。
+pub async fn suggest_user(user: &str, known: &[String]) -> Option<String> {
+    let ranking = match ranking_users().await {
+        Ok(ranking) => ranking,
+        Err(e) => {
+            println!("Failed to fetch ranking for suggestions: {:?}", e);
+            vec![]
+        }
+    };
+    closest(user, known.iter().chain(&ranking))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_ignores_case() {
+        assert_eq!(distance("tourist", "tourist"), 0);
+        assert_eq!(distance("Tourist", "TOURIST"), 0);
+        assert_eq!(distance("tourst", "tourist"), 1);
+        assert_eq!(distance("toruist", "tourist"), 2);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn only_close_names_are_suggested() {
+        let candidates = ["tourist", "Petr", "touristx"].map(String::from);
+        assert_eq!(closest("TOURST", &candidates).as_deref(), Some("tourist"));
+        assert_eq!(closest("petr", &candidates).as_deref(), Some("Petr"));
+        // 編集距離が MAX_DISTANCE を超える名前は提案しません
+        assert_eq!(closest("pe", &candidates).as_deref(), Some("Petr"));
+        assert_eq!(closest("p", &candidates), None);
+        assert_eq!(closest("tourist", &[]), None);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::{
+    api::{self, JudgeStatus},
+    notify,
+};
+use anyhow::Error;
+use itertools::Itertools;
+use poise::serenity_prelude as serenity;
+use serenity::CreateEmbed;
+
+/// 参加者1人分の順位表の行
+#[derive(Debug)]
+struct Standing {
+    user: String,
+    /// 獲得した点数
+    point: f64,
+    /// 解いた問題数
+    solved: usize,
+    /// 開始から最後のACまでの経過時間と、ペナルティの合計 (秒)
+    penalty: i64,
+}
+
+fn format_seconds(seconds: i64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// 提出から順位表を作ります。AtCoderと同じく、点数・解いた問題数の多い順、
+/// 最後のACの時刻にペナルティ (ACまでの誤答の数 × ペナルティ時間) を加えた時間の短い順に並べます。
+/// コンパイルエラーは誤答に数えません。
+fn standings(contest: &api::VirtualContest, submissions: &[api::SubmissionItem]) -> Vec<Standing> {
+    let start = contest.info.start_epoch_second;
+    let end = start + contest.info.duration_second;
+    let points = contest
+        .problems
+        .iter()
+        .map(|p| (p.id.clone(), p.point))
+        .collect::<HashMap<_, _>>();
+
+    contest
+        .participants
+        .iter()
+        .map(|user| {
+            let mut standing = Standing {
+                user: user.clone(),
+                point: 0.0,
+                solved: 0,
+                penalty: 0,
+            };
+            let submissions = submissions
+                .iter()
+                .filter(|s| s.user_id.eq_ignore_ascii_case(user))
+                .filter(|s| (start..end).contains(&s.epoch_second))
+                .sorted_by_key(|s| (s.epoch_second, s.id))
+                .into_group_map_by(|s| s.problem_id.clone());
+            let mut last_ac = 0;
+            let mut wrong = 0;
+            for (problem_id, submissions) in submissions {
+                let Some(ac) = submissions.iter().position(|s| s.result == JudgeStatus::Ac) else {
+                    continue;
+                };
+                standing.solved += 1;
+                standing.point += points
+                    .get(&problem_id)
+                    .copied()
+                    .flatten()
+                    .unwrap_or(submissions[ac].point);
+                last_ac = last_ac.max(submissions[ac].epoch_second - start);
+                wrong += submissions[..ac]
+                    .iter()
+                    .filter(|s| s.result != JudgeStatus::Ce)
+                    .count() as i64;
+            }
+            standing.penalty = last_ac + contest.info.penalty_second * wrong;
+            standing
+        })
+        .sorted_by(|a, b| {
+            b.point
+                .total_cmp(&a.point)
+                .then(b.solved.cmp(&a.solved))
+                .then(a.penalty.cmp(&b.penalty))
+        })
+        .collect()
+}
+
+/// バーチャルコンテストの順位表の埋め込みを作ります。
+/// 参加者が多い場合は、説明の文字数の上限を超えないようページに分けます。
+pub async fn standings_embeds(id: &str) -> Result<Vec<CreateEmbed>, Error> {
+    let contest = api::fetch_virtual_contest(id).await?;
+    let start = contest.info.start_epoch_second;
+    let end = start + contest.info.duration_second;
+    let problem_ids = contest
+        .problems
+        .iter()
+        .map(|p| p.id.clone())
+        .collect::<Vec<_>>();
+
+    let submissions = if contest.participants.is_empty() || problem_ids.is_empty() {
+        vec![]
+    } else {
+        api::fetch_users_and_time(&contest.participants, &problem_ids, start, end).await?
+    };
+    Ok(standings_pages(
+        &contest,
+        &standings(&contest, &submissions),
+    ))
+}
+
+/// 順位表を、説明の文字数の上限を超えないように埋め込みのページに分けます。
+fn standings_pages(contest: &api::VirtualContest, standings: &[Standing]) -> Vec<CreateEmbed> {
+    let lines = standings
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            format!(
+                "{}. {} — {}点 ({}問) {}",
+                i + 1,
+                s.user,
+                s.point,
+                s.solved,
+                format_seconds(s.penalty)
+            )
+        })
+        .collect::<Vec<_>>();
+    let pages = if lines.is_empty() {
+        vec!["参加者がいません。".to_string()]
+    } else {
+        notify::chunk_lines(&lines)
+    };
+
+    pages
+        .into_iter()
+        .map(|description| {
+            CreateEmbed::default()
+                .title(format!("{} の結果", contest.info.title))
+                .url(format!(
+                    "https://kenkoooo.com/atcoder/#/contest/show/{}",
+                    contest.info.id
+                ))
+                .description(description)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn contest(participants: &[&str]) -> api::VirtualContest {
+        api::VirtualContest {
+            info: api::VirtualContestInfo {
+                id: "vc".into(),
+                title: "Virtual".into(),
+                start_epoch_second: 1000,
+                duration_second: 6000,
+                penalty_second: 300,
+            },
+            problems: ["abc300_a", "abc300_b", "abc300_c"]
+                .into_iter()
+                .map(|id| api::VirtualContestProblem {
+                    id: id.into(),
+                    point: None,
+                    order: None,
+                })
+                .collect(),
+            participants: participants.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn standings_use_last_ac_time_plus_penalty() {
+        let submission = fixtures::submission;
+        let submissions = [
+            // alice: 最後のACは 1000 秒後、誤答1回 (CEは数えない)
+            submission(1, "alice", "abc300_a", 1100, JudgeStatus::Ac),
+            submission(2, "alice", "abc300_b", 1200, JudgeStatus::Ce),
+            submission(3, "alice", "abc300_b", 1300, JudgeStatus::Wa),
+            submission(4, "alice", "abc300_b", 2000, JudgeStatus::Ac),
+            // bob: 最後のACは 1500 秒後、誤答なし
+            submission(5, "bob", "abc300_a", 1500, JudgeStatus::Ac),
+            submission(6, "bob", "abc300_b", 2500, JudgeStatus::Ac),
+            // carol: 1問だけ。コンテスト終了後のACは数えない
+            submission(7, "carol", "abc300_a", 1050, JudgeStatus::Ac),
+            submission(8, "carol", "abc300_b", 7000, JudgeStatus::Ac),
+        ];
+        let standings = standings(&contest(&["carol", "bob", "alice"]), &submissions);
+        let standings = standings
+            .iter()
+            .map(|s| (s.user.as_str(), s.point, s.solved, s.penalty))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            standings,
+            [
+                ("alice", 200.0, 2, 1000 + 300),
+                ("bob", 200.0, 2, 1500),
+                ("carol", 100.0, 1, 50),
+            ]
+        );
+    }
+
+    #[test]
+    fn many_participants_are_split_into_pages() {
+        let users = (0..300)
+            .map(|i| format!("participant_{:03}", i))
+            .collect_vec();
+        let contest = contest(&users.iter().map(String::as_str).collect_vec());
+        let pages = standings_pages(&contest, &standings(&contest, &[]));
+        assert!(pages.len() > 1);
+        let descriptions = pages
+            .iter()
+            .map(|page| serde_json::to_value(page).unwrap()["description"].clone())
+            .collect_vec();
+        assert!(descriptions
+            .iter()
+            .all(|d| d.as_str().unwrap().chars().count() <= 4096));
+        assert_eq!(
+            descriptions
+                .iter()
+                .map(|d| d.as_str().unwrap().lines().count())
+                .sum::<usize>(),
+            300
+        );
+
+        let pages = standings_pages(&contest, &[]);
+        assert_eq!(
+            serde_json::to_value(&pages[0]).unwrap()["description"],
+            "参加者がいません。"
+        );
+    }
+}